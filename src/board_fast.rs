@@ -9,6 +9,10 @@ use ggez::GameError;
 
 use crate::util::{self, bitboard_to_vec, BLACK_PAWN_CAPTURES};
 use crate::cpu;
+use crate::fen;
+use crate::magic;
+use crate::pawns;
+use crate::position::{Move, Position};
 
 // https://github.com/ggez/ggez/tree/master/examples
 use ggez::{
@@ -19,8 +23,6 @@ use ggez::{
 };
 use std::ops::{Add, Mul};
 use std::{env, path};
-extern crate bitintr;
-use bitintr::*;
 use std::sync::Once;
 
 pub struct Board {
@@ -32,11 +34,39 @@ pub struct Board {
     pub board_pos: [usize; 64],
     pub white_bitboard: u64,
     pub black_bitboard: u64,
-    // pub pieces_bitboard: [u64; 13],
-    pub score: i32,
+    /// The squares of every kind of piece, indexed like `board_pos` holds them. Index 0 has the empty squares.
+    pub pieces_bitboard: [u64; 13],
+    /// Midgame and endgame scores, positive when white is better. `evaluate` blends them by `phase`.
+    pub score: (i32, i32),
+    /// What `scale_score` added to the blended `score` during a search, nothing outside of one.
+    pub score_scaling: i32,
+    /// What is left of the pieces, see `util::PHASE_WEIGHTS`.
+    pub phase: i32,
+    /// See `pawns::key`.
+    pub pawn_key: u64,
     pub check_for_draws: [u64; 75], //this works for 75 moves.
     pub check_for_draws_idx: usize,
 }
+/// Everything `move_square` changes, so `unmake_move` can put it back without copying the whole board.
+pub struct Undo {
+    mv: Move,
+    piece: usize,
+    captured: usize,
+    /// Where the rook went from and to when the move castled.
+    rook_move: Option<Move>,
+    is_whites_turn: bool,
+    has_moved_king_white: bool,
+    has_moved_king_black: bool,
+    en_passant: usize,
+    score: (i32, i32),
+    phase: i32,
+    pawn_key: u64,
+    white_bitboard: u64,
+    black_bitboard: u64,
+    pieces_bitboard: [u64; 13],
+    check_for_draws_idx: usize,
+}
+
 impl Clone for Board {
     fn clone(&self) -> Board {
         Board {
@@ -46,11 +76,14 @@ impl Clone for Board {
             en_passant: self.en_passant,
             board_pos: self.board_pos,
             score: self.score,
+            score_scaling: self.score_scaling,
+            phase: self.phase,
+            pawn_key: self.pawn_key,
             white_bitboard: self.white_bitboard,
             black_bitboard: self.black_bitboard,
             check_for_draws: self.check_for_draws,
             check_for_draws_idx: self.check_for_draws_idx,
-            // pieces_bitboard: self.pieces_bitboard,
+            pieces_bitboard: self.pieces_bitboard,
         }
     }
 }
@@ -73,6 +106,7 @@ impl Board {
         }
         let white_bitboard = (1..=6).map(|i| pieces_bitboard[i]).reduce(|a, b| a|b).unwrap();
         let black_bitboard = (7..=12).map(|i| pieces_bitboard[i]).reduce(|a, b| a|b).unwrap();
+        let (score, phase) = Board::score_of(&board_pos);
 
         Board {
             board_pos,
@@ -80,12 +114,15 @@ impl Board {
             has_moved_king_black: false,
             en_passant: 0,
             is_whites_turn: true,
-            score: 0,
+            score,
+            score_scaling: 0,
+            phase,
+            pawn_key: pawns::key_of(&board_pos),
             white_bitboard,
             black_bitboard,
             check_for_draws: [0u64; 75],
             check_for_draws_idx: 0usize,
-            // pieces_bitboard,
+            pieces_bitboard,
         }
     }
 
@@ -108,7 +145,7 @@ impl Board {
     }
     pub fn move_square(self: &mut Board, old: usize, new: usize) -> bool {
         let mut piece = self.board_pos[old];
-        self.score -= util::PIECE_VALUES_POSITION[piece][old];
+        let moving_piece = piece;
         let is_white = util::is_piece_white(piece);
         let mut is_reversible_move = true;
         self.en_passant = 0;
@@ -136,7 +173,7 @@ impl Board {
                     }
                 }
                 if util::pos_to_xy(new).1 % 7 == 0 {
-                    piece = 2;
+                    piece = if is_white {crate::QUEEN} else {crate::QUEEN+6};
                 }
             }
             _ => {}
@@ -144,17 +181,23 @@ impl Board {
         let captured_piece = self.board_pos[new];
         let is_capture = captured_piece > 0;
         is_reversible_move &= !is_capture;
-        self.score -= util::PIECE_VALUES_POSITION[captured_piece][new];
-        self.score += util::PIECE_VALUES_POSITION[piece][new];
-        // self.pieces_bitboard[captured_piece] ^= 1<<new;
-        // self.pieces_bitboard[piece] ^= 1<<old;
-        // self.pieces_bitboard[piece] |= 1<<new;
+        self.score.0 += util::PIECE_VALUES_MIDGAME[piece][new] - util::PIECE_VALUES_MIDGAME[moving_piece][old] - util::PIECE_VALUES_MIDGAME[captured_piece][new];
+        self.score.1 += util::PIECE_VALUES_ENDGAME[piece][new] - util::PIECE_VALUES_ENDGAME[moving_piece][old] - util::PIECE_VALUES_ENDGAME[captured_piece][new];
+        self.phase += util::PHASE_WEIGHTS[piece] - util::PHASE_WEIGHTS[moving_piece] - util::PHASE_WEIGHTS[captured_piece];
+        self.pawn_key ^= pawns::key(piece, new) ^ pawns::key(moving_piece, old) ^ pawns::key(captured_piece, new);
+        self.pieces_bitboard[moving_piece] ^= 1<<old;
+        self.pieces_bitboard[crate::NOTHING] |= 1<<old;
+        self.pieces_bitboard[captured_piece] ^= 1<<new;
+        self.pieces_bitboard[piece] |= 1<<new;
         *self.get_friendly_pieces_for_mut(!is_white) &= !(1<<new); //register opponent gone
         *self.get_friendly_pieces_for_mut(is_white) ^= 1<<old; //register piece himself no longer there
         *self.get_friendly_pieces_for_mut(is_white) |= 1<<new; //register piece at new location
 
-        self.check_for_draws[self.check_for_draws_idx] = self.hash_board();
-        self.check_for_draws_idx += 1;
+        // Games longer than the table just stop recording, rather than indexing past its end.
+        if self.check_for_draws_idx < self.check_for_draws.len() {
+            self.check_for_draws[self.check_for_draws_idx] = self.hash_board();
+            self.check_for_draws_idx += 1;
+        }
         if !is_reversible_move {
             self.check_for_draws_idx = 0;
         }
@@ -162,9 +205,129 @@ impl Board {
         self.board_pos[new] = piece;
         self.board_pos[old] = 0;
         self.is_whites_turn = !self.is_whites_turn;
+        debug_assert!(self.is_consistent(), "Bitboards or score don't match board_pos after {}{}", util::pos_to_algebraic(old), util::pos_to_algebraic(new));
         is_capture
     }
-
+    /// The `score` and `phase` of a board from scratch.
+    fn score_of(board_pos: &[usize; 64]) -> ((i32, i32), i32) {
+        (0..64).fold(((0, 0), 0), |((midgame, endgame), phase), pos| {
+            let piece = board_pos[pos];
+            ((midgame + util::PIECE_VALUES_MIDGAME[piece][pos], endgame + util::PIECE_VALUES_ENDGAME[piece][pos]), phase + util::PHASE_WEIGHTS[piece])
+        })
+    }
+    /// Whether the bitboards hold the same pieces as `board_pos` and the score, phase and pawn key add up to them.
+    pub fn is_consistent(&self) -> bool {
+        let mut pieces_bitboard = [0u64; 13];
+        for pos in 0..64 {
+            pieces_bitboard[self.board_pos[pos]] |= 1<<pos;
+        }
+        let white_bitboard = pieces_bitboard[1..=6].iter().fold(0, |all, pieces| all | pieces);
+        let black_bitboard = pieces_bitboard[7..=12].iter().fold(0, |all, pieces| all | pieces);
+        pieces_bitboard == self.pieces_bitboard && white_bitboard == self.white_bitboard && black_bitboard == self.black_bitboard
+            && Board::score_of(&self.board_pos) == (self.score, self.phase) && pawns::key_of(&self.board_pos) == self.pawn_key
+    }
+    /// Every piece of either side attacking `pos`, with the pieces in `occupied` blocking sliders.
+    /// Looks outwards from `pos` with every kind of piece, a knight there seeing an enemy knight means that knight sees `pos` too.
+    /// Pieces left out of `occupied` don't attack, and what is behind them does.
+    pub fn attackers_to(&self, pos: usize, occupied: u64) -> u64 {
+        let pieces = &self.pieces_bitboard;
+        let both = |kind: usize| pieces[kind] | pieces[kind+6];
+        // White pawns attack `pos` from where a black pawn on `pos` would capture, and the other way around.
+        let attackers = (util::BLACK_PAWN_CAPTURES[pos] & pieces[crate::PAWN])
+            | (util::WHITE_PAWN_CAPTURES[pos] & pieces[crate::PAWN+6])
+            | (util::KNIGHT_MOVES[pos] & both(crate::KNIGHT))
+            | (util::KING_MOVES[pos] & both(crate::KING))
+            | (magic::rook_attacks(pos, occupied) & (both(crate::ROOK) | both(crate::QUEEN)))
+            | (magic::bishop_attacks(pos, occupied) & (both(crate::BISHOP) | both(crate::QUEEN)));
+        attackers & occupied
+    }
+    pub fn is_square_attacked(&self, pos: usize, by_white: bool) -> bool {
+        let occupied = self.white_bitboard | self.black_bitboard;
+        self.attackers_to(pos, occupied) & self.get_friendly_pieces_for(by_white) != 0
+    }
+    /// The pieces giving check to the side to move.
+    pub fn checkers(&self) -> u64 {
+        let white = self.is_whites_turn;
+        match Position::find_king(self, white) {
+            Some(king_pos) => self.attackers_to(king_pos, self.white_bitboard | self.black_bitboard) & self.get_friendly_pieces_for(!white),
+            None => 0,
+        }
+    }
+    /// Static exchange evaluation: the material `mv` wins once both sides have captured on its square
+    /// for as long as it pays off, always with their least valuable piece. Negative when the move loses material.
+    /// Sliders behind a capturing piece join in once it has moved. Promotions and en passant aren't counted.
+    pub fn see(&self, (from, to): Move) -> i32 {
+        let value = |piece: usize| util::PIECE_VALUES[piece].abs();
+        let mut gain = [0i32; 32];
+        let mut depth = 0;
+        let mut occupied = self.white_bitboard | self.black_bitboard;
+        let mut attacker = from;
+        let mut attacker_piece = self.board_pos[from];
+        let mut white = util::is_piece_white(attacker_piece);
+        gain[0] = value(self.board_pos[to]);
+        loop {
+            depth += 1;
+            // What the other side gets if it takes back, before knowing whether it can.
+            gain[depth] = value(attacker_piece) - gain[depth-1];
+            if depth == gain.len()-1 {
+                break;
+            }
+            occupied &= !(1<<attacker);
+            white = !white;
+            let attackers = self.attackers_to(to, occupied) & self.get_friendly_pieces_for(white);
+            match self.least_valuable_piece(attackers, white) {
+                Some(pos) => {
+                    attacker = pos;
+                    attacker_piece = self.board_pos[pos];
+                }
+                None => break,
+            }
+        }
+        // Either side may stop capturing when going on would lose.
+        while depth > 1 {
+            depth -= 1;
+            gain[depth-1] = -(-gain[depth-1]).max(gain[depth]);
+        }
+        gain[0]
+    }
+    /// Whether `see` of the move is at least `threshold`.
+    pub fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        self.see(mv) >= threshold
+    }
+    fn least_valuable_piece(&self, among: u64, white: bool) -> Option<usize> {
+        let offset = if white {0} else {6};
+        [crate::PAWN, crate::KNIGHT, crate::BISHOP, crate::ROOK, crate::QUEEN, crate::KING].iter()
+            .map(|&kind| among & self.pieces_bitboard[kind+offset])
+            .find(|&pieces| pieces != 0)
+            .map(|pieces| pieces.trailing_zeros() as usize)
+    }
+    /// Pieces of the side to move that can't leave the line between their king and an enemy slider.
+    pub fn pinned(&self) -> u64 {
+        let white = self.is_whites_turn;
+        let king_pos = match Position::find_king(self, white) {
+            Some(pos) => pos,
+            None => return 0,
+        };
+        let offset = if white {6} else {0};
+        let pieces = &self.pieces_bitboard;
+        let occupied = self.white_bitboard | self.black_bitboard;
+        let enemy_queens = pieces[crate::QUEEN+offset];
+        let mut pinned = 0u64;
+        // Enemy sliders that would see the king on an empty board, and the squares between them and it.
+        let lines: [(u64, fn(usize, u64) -> u64); 2] = [
+            (magic::rook_attacks(king_pos, 0) & (pieces[crate::ROOK+offset] | enemy_queens), magic::rook_attacks),
+            (magic::bishop_attacks(king_pos, 0) & (pieces[crate::BISHOP+offset] | enemy_queens), magic::bishop_attacks),
+        ];
+        for (snipers, attacks) in lines {
+            for sniper in util::BitIter::new(snipers) {
+                let between = attacks(king_pos, 1<<sniper) & attacks(sniper, 1<<king_pos) & occupied;
+                if between.count_ones() == 1 && between & self.get_friendly_pieces_for(white) != 0 {
+                    pinned |= between;
+                }
+            }
+        }
+        pinned
+    }
     pub fn has_king_moved_mut(self: &mut Board, is_white: bool) -> &mut bool {
         if is_white {
             &mut self.has_moved_king_white
@@ -197,20 +360,12 @@ impl Board {
     }
 
     pub fn get_moveable_rook(self: &Board, mut pos: usize, white: bool) -> u64 {
-        let moveable: u64 = util::ROOK_MOVES[pos];
-        let entry_friendly = self.get_friendly_pieces_for(white).pext(moveable) as usize;
-        let entry_enemy = self.get_friendly_pieces_for(!white).pext(moveable) as usize;
-        let mut moveable = util::ROOK_OBSTRUCTION_SELF_MAP[pos][entry_friendly];
-        moveable &= util::ROOK_OBSTRUCTION_OPPONENT_MAP[pos][entry_enemy];
-        moveable
+        let occupied = self.white_bitboard | self.black_bitboard;
+        magic::rook_attacks(pos, occupied) & !self.get_friendly_pieces_for(white)
     }
     pub fn get_moveable_bishop(self: &Board, mut pos: usize, white: bool) -> u64 {
-        let mut moveable: u64 = util::BISHOP_MOVES[pos];
-        let entry_friendly = self.get_friendly_pieces_for(white).pext(moveable) as usize;
-        let entry_enemy = self.get_friendly_pieces_for(!white).pext(moveable) as usize;
-        let mut moveable = util::BISHOP_OBSTRUCTION_SELF_MAP[pos][entry_friendly];
-        moveable &= util::BISHOP_OBSTRUCTION_OPPONENT_MAP[pos][entry_enemy];
-        moveable
+        let occupied = self.white_bitboard | self.black_bitboard;
+        magic::bishop_attacks(pos, occupied) & !self.get_friendly_pieces_for(white)
     }
 
     pub fn get_moveable_queen(self: &Board, mut pos: usize, white: bool) -> u64 {
@@ -228,10 +383,12 @@ impl Board {
             let row_offset: isize = if white {7*8} else {0*8};
             let left_castle:  u64 = util::fix_shl(0b00001110, row_offset);
             let right_castle: u64 = util::fix_shl(0b01100000, row_offset);
-            let can_left_castle = (left_castle & self.get_friendly_pieces_for(white)) == 0 &&
-                util::PIECE_TO_COLOURLESS[self.board_pos[0+row_offset as usize]] == crate::ROOK;
-            let can_right_castle = (right_castle & self.get_friendly_pieces_for(white)) == 0 &&
-                util::PIECE_TO_COLOURLESS[self.board_pos[7+row_offset as usize]] == crate::ROOK;
+            let occupied = self.white_bitboard | self.black_bitboard;
+            let own_rook = if white {crate::ROOK} else {crate::ROOK+6};
+            let can_left_castle = (left_castle & occupied) == 0 &&
+                self.board_pos[0+row_offset as usize] == own_rook;
+            let can_right_castle = (right_castle & occupied) == 0 &&
+                self.board_pos[7+row_offset as usize] == own_rook;
             mov_bits |= (can_left_castle as u64) << (pos as u64 - 2);
             mov_bits |= (can_right_castle as u64) << (pos as u64 + 2);
         }
@@ -254,29 +411,156 @@ impl Board {
         };
         bit_moveable
     }
-    pub fn get_moveable_squares_with_checks(self: &Board, pos: usize) -> u64 {
-        let mut moveable = self.get_moveable_squares(pos);
-        // let is_white = self.white_bitboard & (1 << pos) > 0;
-        // if util::PIECE_TO_COLOURLESS[self.board_pos[pos]] == crate::KING {
-            // moveable &= !(
-                // util::BitIter::new(self.get_friendly_pieces_for(!is_white))
-                // .fold(0u64, |old, pos| old|self.get_moveable_squares(pos))
-            // );
-        // } else {
-            // let king_pos = (0..64usize).filter(|&pos| {
-                // let piece = self.board_pos[pos];
-                // util::PIECE_TO_COLOURLESS[self.board_pos[pos]] == crate::KING
-                // && (util::is_piece_white(piece) == is_white)
-            // }).collect::<Vec<usize>>()[0];
-            // let is_in_check = util::BitIter::new(self.get_friendly_pieces_for(is_white))
-                // .any(|pos| (self.get_moveable_squares(pos) & (1<<king_pos)) > 0);
-            // if is_in_check {
-                // moveable = 0;
-            // }
-        // }
-        moveable
+}
+impl Position for Board {
+    type Undo = Undo;
+
+    fn start_position() -> Board {
+        Board::new()
     }
-    pub fn rate_board(&self) -> i32 {
-        self.score
+    /// Reads a position in Forsyth-Edwards Notation, see `fen::Fen`.
+    fn from_fen(fen: &str) -> Result<Board, String> {
+        let fen = fen::Fen::parse(fen)?;
+        let mut board = Board::new();
+        board.board_pos = fen.board_pos;
+        board.is_whites_turn = fen.is_whites_turn;
+        board.has_moved_king_white = fen.has_moved_king_white;
+        board.has_moved_king_black = fen.has_moved_king_black;
+        board.en_passant = fen.en_passant;
+        board.white_bitboard = 0;
+        board.black_bitboard = 0;
+        board.pieces_bitboard = [0u64; 13];
+        (board.score, board.phase) = Board::score_of(&fen.board_pos);
+        board.pawn_key = pawns::key_of(&fen.board_pos);
+        for pos in 0..64 {
+            let piece = fen.board_pos[pos];
+            board.pieces_bitboard[piece] |= 1<<pos;
+            if piece == 0 {continue;}
+            *board.get_friendly_pieces_for_mut(util::is_piece_white(piece)) |= 1<<pos;
+        }
+        board.check_for_draws_idx = fen.halfmove_clock.min(board.check_for_draws.len()-1);
+        Ok(board)
     }
-}
\ No newline at end of file
+    fn to_fen(&self) -> String {
+        fen::Fen {
+            board_pos: self.board_pos,
+            is_whites_turn: self.is_whites_turn,
+            has_moved_king_white: self.has_moved_king_white,
+            has_moved_king_black: self.has_moved_king_black,
+            en_passant: self.en_passant,
+            halfmove_clock: self.check_for_draws_idx,
+        }.to_string()
+    }
+    fn is_whites_turn(&self) -> bool {
+        self.is_whites_turn
+    }
+    fn piece_at(&self, pos: usize) -> usize {
+        self.board_pos[pos]
+    }
+    fn moveable_squares(&self, pos: usize) -> u64 {
+        self.get_moveable_squares(pos)
+    }
+    fn make_move(&mut self, (from, to): Move) -> Undo {
+        let piece = self.board_pos[from];
+        let (from_x, y) = util::pos_to_xy(from);
+        let to_x = util::pos_to_xy(to).0;
+        let rook_move = if util::PIECE_TO_COLOURLESS[piece] == crate::KING && from_x.abs_diff(to_x) == 2 {
+            Some(((if to_x > from_x {7} else {0}) + y*8, (from+to)/2))
+        } else {
+            None
+        };
+        let undo = Undo {
+            mv: (from, to),
+            piece,
+            captured: self.board_pos[to],
+            rook_move,
+            is_whites_turn: self.is_whites_turn,
+            has_moved_king_white: self.has_moved_king_white,
+            has_moved_king_black: self.has_moved_king_black,
+            en_passant: self.en_passant,
+            score: self.score,
+            phase: self.phase,
+            pawn_key: self.pawn_key,
+            white_bitboard: self.white_bitboard,
+            black_bitboard: self.black_bitboard,
+            pieces_bitboard: self.pieces_bitboard,
+            check_for_draws_idx: self.check_for_draws_idx,
+        };
+        self.move_square(from, to);
+        undo
+    }
+    /// `check_for_draws` only gets written at and after its index, so restoring the index is enough.
+    fn unmake_move(&mut self, undo: Undo) {
+        let (from, to) = undo.mv;
+        if let Some((rook_from, rook_to)) = undo.rook_move {
+            self.board_pos[rook_from] = self.board_pos[rook_to];
+            self.board_pos[rook_to] = 0;
+        }
+        self.board_pos[from] = undo.piece;
+        self.board_pos[to] = undo.captured;
+        self.is_whites_turn = undo.is_whites_turn;
+        self.has_moved_king_white = undo.has_moved_king_white;
+        self.has_moved_king_black = undo.has_moved_king_black;
+        self.en_passant = undo.en_passant;
+        self.score = undo.score;
+        self.phase = undo.phase;
+        self.pawn_key = undo.pawn_key;
+        self.white_bitboard = undo.white_bitboard;
+        self.black_bitboard = undo.black_bitboard;
+        self.pieces_bitboard = undo.pieces_bitboard;
+        self.check_for_draws_idx = undo.check_for_draws_idx;
+        debug_assert!(self.is_consistent(), "Bitboards or score don't match board_pos after unmaking {}{}", util::pos_to_algebraic(from), util::pos_to_algebraic(to));
+    }
+    /// The midgame and endgame scores mixed by how many pieces are left, both kept up to date by `move_square`.
+    fn evaluate(&self) -> i32 {
+        let phase = self.phase.min(util::MAX_PHASE); //promotions can take it past the start.
+        (self.score.0*phase + self.score.1*(util::MAX_PHASE-phase)) / util::MAX_PHASE + self.score_scaling
+    }
+    /// Keeps the scaling apart from `score`, which has to stay what the pieces add up to.
+    fn scale_score(&mut self, factor: f32) -> i32 {
+        let before = self.score_scaling;
+        let blended = self.evaluate() - before;
+        self.score_scaling = ((blended + before) as f32 * factor) as i32 - blended;
+        before
+    }
+    fn restore_score(&mut self, scaling: i32) {
+        self.score_scaling = scaling;
+    }
+    /// From the fields themselves, which is a lot quicker than writing out the FEN.
+    fn hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(&(self.board_pos, self.is_whites_turn, self.has_moved_king_white, self.has_moved_king_black, self.en_passant), &mut hasher);
+        std::hash::Hasher::finish(&hasher)
+    }
+    fn pieces(&self, white: bool) -> u64 {
+        self.get_friendly_pieces_for(white)
+    }
+    fn squares_of(&self, piece: usize) -> u64 {
+        self.pieces_bitboard[piece]
+    }
+    fn phase(&self) -> i32 {
+        self.phase
+    }
+    fn pawn_key(&self) -> u64 {
+        self.pawn_key
+    }
+    fn find_king(&self, white: bool) -> Option<usize> {
+        let kings = self.pieces_bitboard[if white {crate::KING} else {crate::KING+6}];
+        if kings == 0 {None} else {Some(kings.trailing_zeros() as usize)}
+    }
+    fn is_in_check(&self, white: bool) -> bool {
+        match self.find_king(white) {
+            Some(king_pos) => self.is_square_attacked(king_pos, !white),
+            None => false,
+        }
+    }
+    fn checkers(&self) -> u64 {
+        Board::checkers(self)
+    }
+    fn pinned(&self) -> u64 {
+        Board::pinned(self)
+    }
+    fn see_ge(&self, mv: Move, threshold: i32) -> bool {
+        Board::see_ge(self, mv, threshold)
+    }
+}
@@ -9,6 +9,8 @@ use ggez::GameError;
 
 use crate::util;
 use crate::cpu;
+use crate::fen;
+use crate::position::{Move, Position};
 
 // https://github.com/ggez/ggez/tree/master/examples
 use ggez::{
@@ -69,6 +71,7 @@ impl Board {
                     let rook_y = util::pos_to_xy(old).1;
                     let rook_pos = rook_x + rook_y*8;
                     self.move_square(rook_pos, (old+new)/2);
+                    self.is_whites_turn = !self.is_whites_turn; //the rook's move isn't a turn of its own.
                 }
             }
             6 => {
@@ -78,7 +81,7 @@ impl Board {
                     }
                 }
                 if util::pos_to_xy(new).1 % 7 == 0 {
-                    piece = 2;
+                    piece = if util::is_piece_white(piece) {2} else {8};
                 }
             }
             _ => {}
@@ -233,7 +236,7 @@ impl Board {
                 }
                 let look_usize = (x + offset) + y*8;
                 if self.board_pos[look_usize] != 0 {
-                    if util::PIECE_TO_COLOURLESS[self.board_pos[look_usize]] == 5 {
+                    if self.board_pos[look_usize] == if white {5} else {11} {
                         if (look_usize % 8) == 7 {
                             can_castle = true;
                             break;
@@ -253,7 +256,7 @@ impl Board {
                 }
                 let look_usize = (x - offset) + y*8;
                 if self.board_pos[look_usize] != 0 {
-                    if util::PIECE_TO_COLOURLESS[self.board_pos[look_usize]] == 5 {
+                    if self.board_pos[look_usize] == if white {5} else {11} {
                         if x - offset == 0 {
                             can_castle = true;
                             break;
@@ -288,12 +291,54 @@ impl Board {
 
         moveable
     }
-    pub fn rate_board(&self) -> i32 {
-        let mut score: i32 = 0;
-        for pos in 0..64usize {
-            let piece = self.board_pos[pos];
-            score += util::PIECE_VALUES[piece];
-        }
-        score
+}
+
+impl Position for Board {
+    type Undo = Board;
+
+    fn start_position() -> Board {
+        Board::new()
+    }
+    fn from_fen(fen: &str) -> Result<Board, String> {
+        let fen = fen::Fen::parse(fen)?;
+        Ok(Board {
+            board_pos: fen.board_pos,
+            has_moved_king_white: fen.has_moved_king_white,
+            has_moved_king_black: fen.has_moved_king_black,
+            en_passant: fen.en_passant,
+            is_whites_turn: fen.is_whites_turn,
+        })
+    }
+    /// The board doesn't keep a halfmove clock, so it is always 0.
+    fn to_fen(&self) -> String {
+        fen::Fen {
+            board_pos: self.board_pos,
+            is_whites_turn: self.is_whites_turn,
+            has_moved_king_white: self.has_moved_king_white,
+            has_moved_king_black: self.has_moved_king_black,
+            en_passant: self.en_passant,
+            halfmove_clock: 0,
+        }.to_string()
+    }
+    fn is_whites_turn(&self) -> bool {
+        self.is_whites_turn
+    }
+    fn piece_at(&self, pos: usize) -> usize {
+        self.board_pos[pos]
+    }
+    fn moveable_squares(&self, pos: usize) -> u64 {
+        self.get_moveable_squares(pos).into_iter().fold(0u64, |moveable, to| moveable | 1<<to)
     }
-}
\ No newline at end of file
+    fn make_move(&mut self, (from, to): Move) -> Board {
+        let before = self.clone();
+        self.move_square(from, to);
+        before
+    }
+    fn unmake_move(&mut self, undo: Board) {
+        *self = undo;
+    }
+    /// Material only.
+    fn evaluate(&self) -> i32 {
+        self.board_pos.iter().map(|&piece| util::PIECE_VALUES[piece]).sum()
+    }
+}
@@ -0,0 +1,377 @@
+use crate::*;
+use crate::render::Player;
+use crate::config::EngineConfig;
+use std::time::SystemTime;
+
+pub const USAGE: &str = "Usage: chess [command] [flags]
+
+Commands:
+    play [file]       Open the board, optionally loading a saved game (default)
+    uci               Talk UCI on stdin/stdout for chess GUIs
+    perft             Count the positions a few moves deep, per first move
+    bench             Time a search on every position of test/suite.epd
+    suite [file]      Run an EPD test suite, test/suite.epd by default
+    match             Play the engine against the opponent from a book of openings
+    analyze [file]    Print the best move in a position
+    convert <file>    Convert a saved game or PGN to another format
+    difftest          Play random games on both board backends and report where they differ
+    see [file]        Check static exchange evaluation on known positions, test/see.txt by default
+    symmetry          Check that the evaluation scores every position the same with the colours swapped, pawn table included
+    help              Show this message
+
+Flags:
+    --depth <n>       Search this many moves deep (perft: default 4, suite: the config's depth)
+    --movetime <ms>   Spend about this long per move
+    --hash <mb>       Hash table size (accepted for GUIs, the search has no hash table yet)
+    --threads <n>     Games played at once (match, default 1), the search itself is single threaded
+    --fen <fen>       Start from this position
+    --backend <name>  Board to run perft on: fast (default) or slow
+    --pgn <file>      Start from the game in this PGN file
+    --white <who>     human or bot (play)
+    --black <who>     human or bot (play)
+    --time <tc>       Time control like 5+3 or 40/90+30 (play)
+    --games <n>       Number of games (match, difftest and symmetry, default 100)
+    --output <file>   Where to write (convert: default stdout, match: default match.pgn)
+    --book <file>     Openings to play, in SAN or FEN (match, default books/openings.txt)
+    --sprt <e0>,<e1>  Stop once the SPRT decides between these Elo differences (match)
+    --min-score <n>   Fail unless at least this many positions pass (suite)
+    --to <format>     pgn, game or fen (convert, default from --output or pgn)
+    --seed <n>        Seed of the bot's randomness, printed when not given
+    --config <file>   Engine settings, `name = value` lines (or flat JSON)
+    --set <n>=<v>     Change one engine setting, like --set max_depth=6
+    --opponent-config <file>  Settings of the other engine (match)
+    --opponent-set <n>=<v>    Change one setting of the other engine (match)
+
+Engine settings: depth, max_depth, norm_explr_depth, norm, variety.
+Later flags override earlier ones.";
+
+/// Random games longer than this are cut off by `difftest` and `symmetry`.
+const DIFFTEST_MAX_PLIES: usize = 300;
+
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum Command {
+    PLAY,
+    UCI,
+    PERFT,
+    BENCH,
+    SUITE,
+    MATCH,
+    ANALYZE,
+    CONVERT,
+    DIFFTEST,
+    SEE,
+    SYMMETRY,
+    HELP,
+}
+
+impl Command {
+    pub fn parse(text: &str) -> Option<Command> {
+        match text {
+            "play" => Some(Command::PLAY),
+            "uci" => Some(Command::UCI),
+            "perft" => Some(Command::PERFT),
+            "bench" => Some(Command::BENCH),
+            "suite" => Some(Command::SUITE),
+            "match" => Some(Command::MATCH),
+            "analyze" | "analyse" => Some(Command::ANALYZE),
+            "convert" => Some(Command::CONVERT),
+            "difftest" => Some(Command::DIFFTEST),
+            "see" => Some(Command::SEE),
+            "symmetry" => Some(Command::SYMMETRY),
+            "help" | "-h" | "--help" => Some(Command::HELP),
+            _ => None,
+        }
+    }
+}
+
+pub struct Options {
+    pub command: Command,
+    pub depth: Option<usize>,
+    pub movetime_ms: Option<u64>,
+    pub hash_mb: usize,
+    pub threads: usize,
+    pub fen: Option<String>,
+    /// Which `Position` implementation `perft` runs on.
+    pub backend: String,
+    pub pgn: Option<String>,
+    pub player_white: Player,
+    pub player_black: Player,
+    pub time_control: Option<clock::TimeControl>,
+    pub games: usize,
+    pub output: Option<String>,
+    pub format: Option<String>,
+    pub book: Option<String>,
+    pub seed: u64,
+    /// Elo differences of the two hypotheses the SPRT tests.
+    pub sprt: Option<(f64, f64)>,
+    /// Positions of the suite that have to pass for the run to succeed.
+    pub min_score: Option<usize>,
+    pub engine: EngineConfig,
+    /// The engine `match` plays against.
+    pub opponent: EngineConfig,
+    /// Arguments that aren't flags, like the file to load.
+    pub files: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            command: Command::PLAY,
+            depth: None,
+            movetime_ms: None,
+            hash_mb: 16,
+            threads: 1,
+            fen: None,
+            backend: "fast".to_string(),
+            pgn: None,
+            player_white: Player::HUMAN,
+            player_black: Player::BOT,
+            time_control: None,
+            games: 100,
+            output: None,
+            format: None,
+            book: None,
+            seed: cpu::random_seed(),
+            sprt: None,
+            min_score: None,
+            engine: EngineConfig::default(),
+            opponent: EngineConfig::default(),
+            files: vec![],
+        }
+    }
+}
+
+impl Options {
+    /// Reads the arguments after the program name.
+    pub fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options::default();
+        let mut args = args.iter();
+        let mut has_command = false;
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") || arg == "--help" {
+                match Command::parse(arg) {
+                    Some(command) if !has_command => {
+                        options.command = command;
+                        has_command = true;
+                    }
+                    _ => options.files.push(arg.clone()),
+                }
+                continue;
+            }
+            let value = args.next().ok_or(format!("{} needs a value", arg))?;
+            match arg.as_str() {
+                "--depth" => options.depth = Some(parse_number(arg, value)?),
+                "--movetime" => options.movetime_ms = Some(parse_number(arg, value)?),
+                "--hash" => options.hash_mb = parse_number(arg, value)?,
+                "--threads" => options.threads = parse_number(arg, value)?,
+                "--fen" => options.fen = Some(value.clone()),
+                "--backend" => options.backend = value.clone(),
+                "--pgn" => options.pgn = Some(value.clone()),
+                "--white" => options.player_white = parse_player(value)?,
+                "--black" => options.player_black = parse_player(value)?,
+                "--time" => options.time_control = Some(clock::TimeControl::parse(value)
+                    .ok_or(format!("Couldn't read time control {}, expected something like 5+3 or 40/90+30", value))?),
+                "--games" => options.games = parse_number(arg, value)?,
+                "--output" => options.output = Some(value.clone()),
+                "--to" => options.format = Some(value.clone()),
+                "--seed" => options.seed = parse_number(arg, value)?,
+                "--book" => options.book = Some(value.clone()),
+                "--sprt" => options.sprt = match value.split_once(',') {
+                    Some((elo0, elo1)) => Some((parse_number(arg, elo0)?, parse_number(arg, elo1)?)),
+                    None => return Err(format!("--sprt expects two Elo differences like 0,10, got {}", value)),
+                },
+                "--min-score" => options.min_score = Some(parse_number(arg, value)?),
+                "--config" => options.engine = EngineConfig::read(value)?,
+                "--set" => set_engine_option(&mut options.engine, value)?,
+                "--opponent-config" => options.opponent = EngineConfig::read(value)?,
+                "--opponent-set" => set_engine_option(&mut options.opponent, value)?,
+                _ => return Err(format!("Unknown flag {}", arg)),
+            }
+        }
+        Ok(options)
+    }
+    pub fn search_limits(&self) -> cpu::SearchLimits {
+        match (self.depth, self.movetime_ms) {
+            (Some(depth), _) => cpu::SearchLimits::depth(depth),
+            (None, Some(movetime)) => cpu::SearchLimits::think_time(movetime),
+            (None, None) => cpu::SearchLimits {depth: None, iteration_limit_ms: render::BOT_STRENGTHS[render::DEFAULT_BOT_STRENGTH].1},
+        }
+    }
+    /// The game given by `--fen`, `--pgn` or a file argument, the initial position otherwise.
+    pub fn load_game(&self) -> Result<slots::SavedGame, String> {
+        if let Some(fen) = &self.fen {
+            return Ok(slots::SavedGame::new(Board::from_fen(fen)?, vec![]));
+        }
+        if let Some(path) = &self.pgn {
+            return read_pgn(path);
+        }
+        match self.files.first() {
+            Some(path) if path.ends_with(".pgn") => read_pgn(path),
+            Some(path) => slots::SavedGame::read(path),
+            None => Ok(slots::SavedGame::new(Board::new(), vec![])),
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value.parse::<T>().map_err(|_| format!("{} expects a number, got {}", flag, value))
+}
+
+fn set_engine_option(config: &mut EngineConfig, value: &str) -> Result<(), String> {
+    match value.split_once('=') {
+        Some((name, value)) => config.set(name, value),
+        None => Err(format!("Expected name=value, got {}", value)),
+    }
+}
+
+fn parse_player(value: &str) -> Result<Player, String> {
+    match value {
+        "human" | "h" => Ok(Player::HUMAN),
+        "bot" | "b" => Ok(Player::BOT),
+        _ => Err(format!("Expected human or bot, got {}", value)),
+    }
+}
+
+fn read_pgn(path: &str) -> Result<slots::SavedGame, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let (start_board, history) = notation::parse_pgn(&contents)?;
+    Ok(slots::SavedGame::new(start_board, history))
+}
+
+/// Runs the command given on the command line. Only `play` opens a window.
+pub fn run() {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            println!("{}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let result = match options.command {
+        Command::PLAY => render::main(&options).map_err(|e| e.to_string()),
+        Command::UCI => {
+            uci::run(&options);
+            Ok(())
+        }
+        Command::PERFT => perft(&options),
+        Command::BENCH => {
+            println!("Seed: {}", options.seed);
+            println!("Sliding pieces use {}.", if magic::uses_pext() {"PEXT"} else {"magic bitboards"});
+            test::benchmark_performance(&options.search_limits(), &mut cpu::SearchContext::new(options.engine.clone(), options.seed));
+            Ok(())
+        }
+        Command::SUITE => suite(&options),
+        Command::MATCH => match_runner::run(&options),
+        Command::ANALYZE => options.load_game().map(|game| analyze(&game.final_board(), &options.search_limits(), &mut cpu::SearchContext::new(options.engine.clone(), options.seed))),
+        Command::CONVERT => convert(&options),
+        Command::DIFFTEST => {
+            println!("Seed: {}", options.seed);
+            match test::differential_test::<Board, board_slow::Board>(options.games, DIFFTEST_MAX_PLIES, options.seed) {
+                true => Ok(()),
+                false => Err(format!("\nThe board backends differ, rerun with --seed {}", options.seed)),
+            }
+        }
+        Command::SEE => test::see_suite(options.files.first().map(|path| path.as_str()).unwrap_or(test::SEE_SUITE)).and_then(|all_passed| match all_passed {
+            true => Ok(()),
+            false => Err("\nSome exchanges came out different".to_string()),
+        }),
+        Command::SYMMETRY => {
+            println!("Seed: {}", options.seed);
+            match test::symmetry_test::<Board>(options.games, DIFFTEST_MAX_PLIES, options.seed) {
+                true => Ok(()),
+                false => Err(format!("\nThe evaluation isn't symmetric, rerun with --seed {}", options.seed)),
+            }
+        }
+        Command::HELP => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        println!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn suite(options: &cli::Options) -> Result<(), String> {
+    let positions = epd::read_epd(options.files.first().map(|path| path.as_str()).unwrap_or(epd::DEFAULT_SUITE))?;
+    // A fixed depth unless asked otherwise, so results don't depend on the machine.
+    let limits = if options.depth.is_none() && options.movetime_ms.is_none() {
+        cpu::SearchLimits::depth(options.engine.depth)
+    } else {
+        options.search_limits()
+    };
+    // Without variety the same engine always plays the same moves, so a failing position stays failing.
+    let mut config = options.engine.clone();
+    config.variety = 0;
+    let passed = test::run_suite(&positions, &limits, &mut cpu::SearchContext::new(config, options.seed));
+    match options.min_score {
+        Some(min_score) if passed < min_score => Err(format!("\nPassed {} positions, --min-score is {}", passed, min_score)),
+        _ => Ok(()),
+    }
+}
+
+fn perft(options: &cli::Options) -> Result<(), String> {
+    let fen = options.load_game()?.final_board().to_fen();
+    let depth = options.depth.unwrap_or(4);
+    match options.backend.as_str() {
+        "fast" => test::perft_divide(&board_fast::Board::from_fen(&fen)?, depth),
+        "slow" => test::perft_divide(&board_slow::Board::from_fen(&fen)?, depth),
+        other => return Err(format!("Unknown backend {}, expected fast or slow", other)),
+    }
+    Ok(())
+}
+
+fn analyze(board: &Board, limits: &cpu::SearchLimits, ctx: &mut cpu::SearchContext) {
+    println!("{}", board.to_fen());
+    println!("Seed: {}", ctx.seed);
+    if !board.has_legal_moves() {
+        println!("No legal moves, {}.", if board.is_in_check(board.is_whites_turn) {"checkmate"} else {"stalemate"});
+        return;
+    }
+    let bef = SystemTime::now();
+    let result = cpu::search(&mut board.clone(), limits, ctx);
+    let (from, to) = result.best_move;
+    println!("Best move: {} ({})", notation::move_to_san(board, from, to), notation::move_to_uci(board, from, to));
+    println!("Score: {} for the side to move", result.score);
+    println!("Depth: {}. Evaluations: {}. Took {}ms", result.depth, result.evaluations, bef.elapsed().unwrap().as_millis());
+}
+
+fn convert(options: &cli::Options) -> Result<(), String> {
+    if options.files.is_empty() && options.fen.is_none() && options.pgn.is_none() {
+        return Err("convert needs a file to read".to_string());
+    }
+    let game = options.load_game()?;
+    let format = match (&options.format, &options.output) {
+        (Some(format), _) => format.clone(),
+        (None, Some(output)) if output.ends_with(".txt") => "game".to_string(),
+        (None, Some(output)) if output.ends_with(".fen") => "fen".to_string(),
+        _ => "pgn".to_string(),
+    };
+    let text = match format.as_str() {
+        "pgn" => {
+            let board = game.final_board();
+            let result = if board.has_legal_moves() {
+                "*"
+            } else if !board.is_in_check(board.is_whites_turn) {
+                "1/2-1/2"
+            } else if board.is_whites_turn {
+                "0-1"
+            } else {
+                "1-0"
+            };
+            notation::to_pgn(&game.start_board, &game.history, &[], result)
+        }
+        "game" => game.to_file_string(),
+        "fen" => format!("{}\n", game.final_board().to_fen()),
+        _ => return Err(format!("Unknown format {}, expected pgn, game or fen", format)),
+    };
+    match &options.output {
+        Some(path) => std::fs::write(path, text).map_err(|e| format!("Failed to write {}: {}", path, e)),
+        None => {
+            print!("{}", text);
+            Ok(())
+        }
+    }
+}
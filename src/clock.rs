@@ -0,0 +1,155 @@
+use std::time::Instant;
+
+/// `base_ms` for the first `moves_per_period` moves, which is added again every period.
+/// A `moves_per_period` of 0 means the base time is for the whole game.
+#[derive(Clone, Copy, PartialEq)]
+pub struct TimeControl {
+    pub base_ms: u64,
+    pub increment_ms: u64,
+    pub moves_per_period: usize,
+}
+impl TimeControl {
+    /// Parses `minutes+increment seconds`, optionally prefixed with `moves/`, like `5+3` or `40/90+30`.
+    pub fn parse(text: &str) -> Option<TimeControl> {
+        let (moves_per_period, rest) = match text.split_once('/') {
+            Some((moves, rest)) => (moves.trim().parse::<usize>().ok()?, rest),
+            None => (0, text),
+        };
+        let (base, increment) = match rest.split_once('+') {
+            Some((base, increment)) => (base, increment),
+            None => (rest, "0"),
+        };
+        let base_minutes = base.trim().parse::<f64>().ok()?;
+        let increment_seconds = increment.trim().parse::<f64>().ok()?;
+        if base_minutes <= 0.0 || increment_seconds < 0.0 {
+            return None;
+        }
+        Some(TimeControl {
+            base_ms: (base_minutes * 60_000.0) as u64,
+            increment_ms: (increment_seconds * 1000.0) as u64,
+            moves_per_period,
+        })
+    }
+}
+impl std::fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.moves_per_period > 0 {
+            write!(f, "{}/", self.moves_per_period)?;
+        }
+        write!(f, "{}+{}", self.base_ms as f64 / 60_000.0, self.increment_ms as f64 / 1000.0)
+    }
+}
+
+#[derive(Clone)]
+pub struct Clock {
+    pub time_control: TimeControl,
+    /// Time left at the start of the current turn, white first.
+    pub remaining_ms: [u64; 2],
+    pub moves_made: [usize; 2],
+    pub whites_turn: bool,
+    turn_started: Option<Instant>,
+}
+fn side(white: bool) -> usize {
+    (!white) as usize
+}
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Clock {
+        Clock {
+            time_control,
+            remaining_ms: [time_control.base_ms; 2],
+            moves_made: [0; 2],
+            whites_turn: true,
+            turn_started: None,
+        }
+    }
+    /// Starts the clock of the side to move.
+    pub fn start(&mut self, whites_turn: bool) {
+        self.whites_turn = whites_turn;
+        self.turn_started = Some(Instant::now());
+    }
+    pub fn stop(&mut self) {
+        let white = self.whites_turn;
+        self.remaining_ms[side(white)] = self.remaining(white);
+        self.turn_started = None;
+    }
+    /// A stopped copy, holding the time left right now.
+    pub fn snapshot(&self) -> Clock {
+        let mut snapshot = self.clone();
+        snapshot.stop();
+        snapshot
+    }
+    pub fn is_running(&self) -> bool {
+        self.turn_started.is_some()
+    }
+    pub fn remaining(&self, white: bool) -> u64 {
+        let remaining = self.remaining_ms[side(white)];
+        match self.turn_started {
+            Some(started) if white == self.whites_turn => remaining.saturating_sub(started.elapsed().as_millis() as u64),
+            _ => remaining,
+        }
+    }
+    pub fn has_flagged(&self, white: bool) -> bool {
+        self.remaining(white) == 0
+    }
+    /// Called after `white` made a move: stops their clock, adds the increment and starts the opponent's.
+    pub fn press(&mut self, white: bool) {
+        let remaining = self.remaining(white);
+        let idx = side(white);
+        self.moves_made[idx] += 1;
+        self.remaining_ms[idx] = remaining + self.time_control.increment_ms;
+        let period = self.time_control.moves_per_period;
+        if period > 0 && self.moves_made[idx] % period == 0 {
+            self.remaining_ms[idx] += self.time_control.base_ms;
+        }
+        if self.is_running() {
+            self.start(!white);
+        } else {
+            self.whites_turn = !white;
+        }
+    }
+    /// How long `white` should spend on its next move.
+    pub fn think_time_ms(&self, white: bool) -> u64 {
+        let period = self.time_control.moves_per_period;
+        let moves_to_go = if period > 0 {
+            period - self.moves_made[side(white)] % period
+        } else {
+            30
+        };
+        think_time_ms(self.remaining(white), self.time_control.increment_ms, moves_to_go)
+    }
+    /// Single line used in saved games, see `from_save_line`.
+    pub fn to_save_line(&self) -> String {
+        format!("clock {} {} {} {} {} {} {}",
+            self.time_control.base_ms, self.time_control.increment_ms, self.time_control.moves_per_period,
+            self.remaining(true), self.remaining(false), self.moves_made[0], self.moves_made[1])
+    }
+    pub fn from_save_line(line: &str) -> Option<Clock> {
+        let numbers: Vec<u64> = line.split_whitespace().skip(1).map(|n| n.parse::<u64>()).collect::<Result<_, _>>().ok()?;
+        if numbers.len() != 7 {
+            return None;
+        }
+        let time_control = TimeControl {base_ms: numbers[0], increment_ms: numbers[1], moves_per_period: numbers[2] as usize};
+        Some(Clock {
+            time_control,
+            remaining_ms: [numbers[3], numbers[4]],
+            moves_made: [numbers[5] as usize, numbers[6] as usize],
+            whites_turn: true,
+            turn_started: None,
+        })
+    }
+}
+
+/// How long to spend on a move with `remaining` on the clock and `moves_to_go` until more time is added.
+pub fn think_time_ms(remaining: u64, increment_ms: u64, moves_to_go: usize) -> u64 {
+    (remaining / moves_to_go.max(1) as u64 + increment_ms * 3/4).min(remaining / 2)
+}
+
+/// `m:ss`, with tenths of a second once under ten seconds.
+pub fn format_time(ms: u64) -> String {
+    if ms < 10_000 {
+        format!("0:{:02}.{}", ms / 1000, (ms % 1000) / 100)
+    } else {
+        let seconds = ms / 1000;
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
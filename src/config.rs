@@ -0,0 +1,88 @@
+use std::fmt;
+
+/// Settings of the search, so experiments don't need a recompile and both sides of a match can differ.
+#[derive(Clone, PartialEq, Debug)]
+pub struct EngineConfig {
+    /// Depth the iterative deepening starts at, captures don't count towards it.
+    pub depth: usize,
+    /// Depth no line is searched beyond, captures included. Grows along with `depth`.
+    pub max_depth: usize,
+    /// Depth of the quick search that sets the pruning norm.
+    pub norm_explr_depth: usize,
+    /// How far below the quick search's score a line may drop before it stops being searched.
+    pub norm: i32,
+    /// Up to how many centipawns of noise the moves at the root get, 0 always plays the best move.
+    pub variety: i32,
+}
+
+impl Default for EngineConfig {
+    fn default() -> EngineConfig {
+        EngineConfig {
+            depth: 4,
+            max_depth: 5,
+            norm_explr_depth: 2,
+            norm: 340,
+            variety: 10,
+        }
+    }
+}
+
+impl EngineConfig {
+    /// Changes one setting. `name` ignores case, spaces and underscores, so `max_depth` and UCI's `MaxDepth` both work.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let key: String = name.chars().filter(|c| *c != '_' && *c != ' ' && *c != '-').collect::<String>().to_lowercase();
+        let value = value.trim();
+        let number = || value.parse::<i64>().map_err(|_| format!("{} expects a number, got {}", name, value));
+        match key.as_str() {
+            "depth" => self.depth = number()?.max(1) as usize,
+            "maxdepth" => self.max_depth = number()?.max(1) as usize,
+            "normexplrdepth" => self.norm_explr_depth = number()?.max(1) as usize,
+            "norm" => self.norm = number()? as i32,
+            "variety" => self.variety = number()?.max(0) as i32,
+            _ => return Err(format!("Unknown engine setting {}", name)),
+        }
+        Ok(())
+    }
+    /// Reads `name = value` lines, a flat TOML table. Flat JSON objects are read as well,
+    /// since braces, quotes and commas are skipped and `:` works like `=`.
+    pub fn parse(text: &str) -> Result<EngineConfig, String> {
+        let mut config = EngineConfig::default();
+        for line in text.lines().flat_map(|line| line.split(',')) {
+            let line = line.split('#').next().unwrap_or("");
+            let line: String = line.chars().filter(|c| !matches!(c, '{' | '}' | '"')).collect();
+            if line.trim().is_empty() || line.trim().starts_with('[') {
+                continue;
+            }
+            match line.split_once(['=', ':']) {
+                Some((name, value)) => config.set(name.trim(), value)?,
+                None => return Err(format!("Expected `name = value`, got `{}`", line.trim())),
+            }
+        }
+        Ok(config)
+    }
+    /// The `option` lines a UCI engine announces, with these settings as defaults.
+    pub fn uci_options(&self) -> Vec<String> {
+        vec![
+            format!("option name Depth type spin default {} min 1 max 20", self.depth),
+            format!("option name MaxDepth type spin default {} min 1 max 30", self.max_depth),
+            format!("option name NormExplrDepth type spin default {} min 1 max 10", self.norm_explr_depth),
+            format!("option name Norm type spin default {} min -100000 max 100000", self.norm),
+            format!("option name Variety type spin default {} min 0 max 1000", self.variety),
+        ]
+    }
+    pub fn read(path: &str) -> Result<EngineConfig, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        EngineConfig::parse(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+/// Writes the config in the format `parse` reads.
+impl fmt::Display for EngineConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "depth = {}", self.depth)?;
+        writeln!(f, "max_depth = {}", self.max_depth)?;
+        writeln!(f, "norm_explr_depth = {}", self.norm_explr_depth)?;
+        writeln!(f, "norm = {}", self.norm)?;
+        writeln!(f, "variety = {}", self.variety)
+    }
+}
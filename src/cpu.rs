@@ -1,14 +1,37 @@
-use crate::{*, util::BitIter};
+use crate::{*, util::BitIter, config::EngineConfig};
 use std::hash;
 use std::time::{SystemTime, UNIX_EPOCH};
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+/// What a search carries along while walking the tree.
+pub struct SearchContext {
+    pub config: EngineConfig,
+    pub evaluations: usize,
+    /// Seeded, so a search can be repeated move for move.
+    pub rng: StdRng,
+    pub seed: u64,
+    pub pawn_table: pawns::PawnTable,
+}
+
+impl SearchContext {
+    pub fn new(config: EngineConfig, seed: u64) -> SearchContext {
+        SearchContext {config, evaluations: 0, rng: StdRng::seed_from_u64(seed), seed, pawn_table: pawns::PawnTable::new()}
+    }
+}
 
-pub fn calculate_best_move(board: &mut Board, evaluations: &mut usize, depth: usize, max_depth: usize, norm: i32, is_cpu_white: bool, beta: bool, rng: &mut ThreadRng) -> (i32, (usize, usize)) {
-    *evaluations += 1;
-    let mut move_score = board.rate_board();
-    move_score *= (board.is_whites_turn as i32)*2-1;
+/// A seed for when none was given, print it so the run can be repeated.
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+/// Deeper down a move that leaves the king in check loses it next ply, but `is_root` only tries legal moves
+/// as the move found there gets played. It also adds the config's variety to their scores, so the bot doesn't
+/// play the same game every time.
+pub fn calculate_best_move<P: Position>(board: &mut P, ctx: &mut SearchContext, depth: usize, max_depth: usize, norm: i32, is_cpu_white: bool, is_root: bool) -> (i32, (usize, usize)) {
+    ctx.evaluations += 1;
+    let mut move_score = board.evaluate_with_pawns(&mut ctx.pawn_table);
+    move_score *= (board.is_whites_turn() as i32)*2-1;
     if depth <= 0 || max_depth <= 0 || (move_score < norm && depth < 3) {
         // move_score += 100*max_depth as i32; //To make it prefer short term things.
         return (move_score, (64,64));
@@ -16,84 +39,115 @@ pub fn calculate_best_move(board: &mut Board, evaluations: &mut usize, depth: us
     let mut best_move: (usize, usize) = (64, 64);
     let mut best_move_score: i32 = std::i32::MIN / 10;
 
-    let mut total_options: u32 = 0;
-    
-    for pos in util::BitIter::new(board.get_friendly_pieces_for(board.is_whites_turn)) {
-        let options_timer = profiler::start_timing("options_searching");
-        let options = board.get_moveable_squares(pos);
-        options_timer.stop();
-        total_options += options.count_ones();
-        for target in util::BitIter::new(options) {
-            let clone_timer = profiler::start_timing("clone");
-            let mut board_2 = board.clone();
-            board_2.score = (board.score as f32 * 1.01) as i32;
-            clone_timer.stop();
+    let options_timer = profiler::start_timing("options_searching");
+    let options = if is_root {board.legal_moves()} else {board.pseudo_legal_moves()};
+    options_timer.stop();
+    let total_options = options.len();
+    // Scores further down weigh a little more, the same for every move from here.
+    let scaling = board.scale_score(1.01);
 
-            //check for draws.
-            let hash_board = board_2.hash_board();
-            let mut is_game_finished: bool = false;
-            // for i in 0..=board_2.check_for_draws_idx {
-            //     if board_2.check_for_draws[i] == hash_board {
-            //         is_game_finished = true;
-            //         board_2.score = 0;
-            //     }
-            // }
+    for (pos, target) in options {
+        let is_capture = board.piece_at(target) != 0;
+        // From here on only captures are followed further, and ones that lose material aren't worth it.
+        if depth == 1 && is_capture && !board.see_ge((pos, target), 0) {
+            continue;
+        }
+        let move_timer = profiler::start_timing("move_piece");
+        let undo = board.make_move((pos, target));
+        move_timer.stop();
 
-            let nothing = profiler::start_timing("nothing");
-            nothing.stop();
+        let (mut move_score, _) =
+            calculate_best_move(board, ctx, depth-(!is_capture as usize), max_depth-1, norm*-1, is_cpu_white, false);
+        move_score *= -1;
 
-            let move_timer = profiler::start_timing("move_piece");
-            let is_capture = board_2.move_square(pos, target);
-            move_timer.stop();
+        let unmove_timer = profiler::start_timing("unmake_move");
+        board.unmake_move(undo);
+        unmove_timer.stop();
 
-            if !is_game_finished {
-                let (mut move_score, _) = 
-                    calculate_best_move(&mut board_2, evaluations, depth-(!is_capture as usize), max_depth-1, norm*-1, is_cpu_white, beta, rng);
-                move_score *= -1;
-            }
-            if move_score > best_move_score || (crate::RANDOM && move_score == best_move_score && rng.gen_bool(0.5)){
-                best_move = (pos, target);
-                best_move_score = move_score;
-            }
+        if is_root && ctx.config.variety > 0 {
+            move_score += ctx.rng.gen_range(0..=ctx.config.variety);
+        }
+        if move_score > best_move_score {
+            best_move = (pos, target);
+            best_move_score = move_score;
         }
     }
-    // if max_depth > depth {
-    //     let mut board_2 = board.clone();
-    //     board_2.score = (board.score as f32 * 1.01) as i32;
-    //     board_2.move_square(best_move.0, best_move.1);
-    //     (move_score, _) = calculate_best_move(&mut &mut board_2, depth, max_depth-1, norm*-1, is_cpu_white);
-    // }
-    
+    board.restore_score(scaling);
+
     (best_move_score + total_options as i32, best_move)
 }
 
-pub fn make_bot_move(game: &mut crate::render::Game, beta: bool) {
-    let mut RNG = rand::thread_rng();
-    let total_timer = profiler::start_timing("total");
-    let is_cpu_white = game.board.is_whites_turn;
-    let mut evaluations: usize = 0;
-    let (mut norm, _) = cpu::calculate_best_move(&mut game.board, &mut evaluations, crate::NORM_EXPLR_DEPTH, crate::NORM_EXPLR_DEPTH, -100_000, is_cpu_white, beta, &mut RNG);
-    norm -= crate::NORM;
+/// How far the bot may search.
+pub struct SearchLimits {
+    /// Search this deep in one go instead of deepening against the clock.
+    pub depth: Option<usize>,
+    /// Stop deepening once one iteration took longer than this.
+    pub iteration_limit_ms: u64,
+}
+impl SearchLimits {
+    pub fn depth(depth: usize) -> SearchLimits {
+        SearchLimits {depth: Some(depth), iteration_limit_ms: 0}
+    }
+    /// Limits for spending about `think_ms` on a move. The next iteration
+    /// takes a multiple of the last, so we stop well before it would overrun.
+    pub fn think_time(think_ms: u64) -> SearchLimits {
+        SearchLimits {depth: None, iteration_limit_ms: think_ms / 8}
+    }
+}
+
+pub struct SearchResult {
+    pub score: i32,
+    pub best_move: (usize, usize),
+    pub depth: usize,
+    pub evaluations: usize,
+}
+
+/// Finds the best move for the side to move, without touching any `Game`.
+pub fn search<P: Position>(board: &mut P, limits: &SearchLimits, ctx: &mut SearchContext) -> SearchResult {
+    let is_cpu_white = board.is_whites_turn();
+    ctx.evaluations = 0;
+    let (mut norm, _) = cpu::calculate_best_move(board, ctx, ctx.config.norm_explr_depth, ctx.config.norm_explr_depth, -100_000, is_cpu_white, false);
+    norm -= ctx.config.norm;
     // println!("{}", norm);
-    let mut depth = crate::DEPTH;
-    let mut max_depth = crate::MAX_DEPTH;
+    let mut depth = limits.depth.unwrap_or(ctx.config.depth);
+    let mut max_depth = (depth + ctx.config.max_depth).saturating_sub(ctx.config.depth).max(1);
     let mut score ;
-    let mut from;
-    let mut to;
+    let mut best_move;
     loop {
         let bef = SystemTime::now();
-        (score, (from, to)) = cpu::calculate_best_move(&mut game.board, &mut evaluations, depth, max_depth, norm, is_cpu_white, beta, &mut RNG);
-        if bef.elapsed().unwrap().as_millis() > 100 {
+        (score, best_move) = cpu::calculate_best_move(board, ctx, depth, max_depth, norm, is_cpu_white, true);
+        if limits.depth.is_some() || bef.elapsed().unwrap().as_millis() as u64 > limits.iteration_limit_ms {
             break;
         }
         depth += 1;
         max_depth += 1;
-    }    
-    println!("Depth: {}. Evaluations: {}M", depth, evaluations as f32 / 1_000_000.0);
+    }
+    // Shallow searches can prune the root itself, rather play any legal move than none.
+    if best_move.0 >= 64 {
+        if let Some(&legal_move) = board.legal_moves().first() {
+            best_move = legal_move;
+        }
+    }
+    SearchResult {score, best_move, depth, evaluations: ctx.evaluations}
+}
+
+pub fn make_bot_move<P: Position>(game: &mut crate::render::Game<P>) {
+    let total_timer = profiler::start_timing("total");
+    let is_cpu_white = game.board.is_whites_turn();
+    // Without a clock we deepen until one iteration takes `bot_think_ms`.
+    let limits = match &game.clock {
+        Some(clock) => SearchLimits::think_time(clock.think_time_ms(is_cpu_white)),
+        None => SearchLimits {depth: None, iteration_limit_ms: game.bot_think_ms},
+    };
+    let result = search(&mut game.board, &limits, &mut game.search_context);
+    println!("Depth: {}. Evaluations: {}M", result.depth, result.evaluations as f32 / 1_000_000.0);
 
     total_timer.stop();
-    println!("CPU score is: {}", score);
-    game.move_square(from, to);
-    println!("CPU score rn is: {}", game.board.rate_board());
+    println!("CPU score is: {}", result.score);
+    if result.best_move.0 >= 64 {
+        return; //checkmated or stalemated, nothing to play.
+    }
+    game.move_square(result.best_move.0, result.best_move.1);
+    println!("CPU score rn is: {}", game.board.evaluate());
     profiler::print();
-}
\ No newline at end of file
+}
@@ -0,0 +1,86 @@
+use crate::*;
+
+pub const DEFAULT_SUITE: &str = "test/suite.epd";
+
+/// A test position in Extended Position Description: a FEN without the move counters,
+/// followed by operations like `bm Kxf2; id "check";`.
+pub struct EpdPosition {
+    pub id: String,
+    pub board: Board,
+    /// Moves that pass the test (`bm`), any of them will do.
+    pub best_moves: Vec<(usize, usize)>,
+    /// Moves that fail the test (`am`).
+    pub avoid_moves: Vec<(usize, usize)>,
+    pub comment: String,
+}
+
+impl EpdPosition {
+    pub fn parse(line: &str) -> Result<EpdPosition, String> {
+        let fields: Vec<&str> = line.splitn(5, ' ').collect();
+        if fields.len() < 4 {
+            return Err(format!("Expected a position, got `{}`", line));
+        }
+        let board = Board::from_fen(&fields[..4].join(" "))?;
+        let mut position = EpdPosition {id: String::new(), board, best_moves: vec![], avoid_moves: vec![], comment: String::new()};
+        for operation in split_operations(fields.get(4).unwrap_or(&"")) {
+            let (opcode, operands) = operation.split_once(' ').unwrap_or((operation.as_str(), ""));
+            let operands = operands.trim();
+            match opcode {
+                "bm" => position.best_moves = parse_moves(&position.board, operands)?,
+                "am" => position.avoid_moves = parse_moves(&position.board, operands)?,
+                "id" => position.id = operands.trim_matches('"').to_string(),
+                "c0" => position.comment = operands.trim_matches('"').to_string(),
+                _ => {}, //other opcodes aren't used by the suite.
+            }
+        }
+        if position.best_moves.is_empty() && position.avoid_moves.is_empty() {
+            return Err(format!("{} has neither bm nor am", position.id));
+        }
+        Ok(position)
+    }
+    /// Whether the engine playing `chosen` passes this test.
+    pub fn passes(&self, chosen: (usize, usize)) -> bool {
+        (self.best_moves.is_empty() || self.best_moves.contains(&chosen)) && !self.avoid_moves.contains(&chosen)
+    }
+}
+
+/// Splits on the `;` ending every operation, except inside quoted strings.
+fn split_operations(text: &str) -> Vec<String> {
+    let mut operations = vec![];
+    let mut operation = String::new();
+    let mut in_string = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                in_string = !in_string;
+                operation.push(c);
+            }
+            ';' if !in_string => {
+                if !operation.trim().is_empty() {
+                    operations.push(operation.trim().to_string());
+                }
+                operation.clear();
+            }
+            _ => operation.push(c),
+        }
+    }
+    if !operation.trim().is_empty() {
+        operations.push(operation.trim().to_string());
+    }
+    operations
+}
+
+fn parse_moves(board: &Board, operands: &str) -> Result<Vec<(usize, usize)>, String> {
+    operands.split_whitespace()
+        .map(|san| notation::san_to_move(board, san).ok_or(format!("Illegal or unsupported move {} in {}", san, board.to_fen())))
+        .collect()
+}
+
+/// Every position of an EPD file, blank lines and `#` comments skipped.
+pub fn read_epd(path: &str) -> Result<Vec<EpdPosition>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    text.lines().enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim().starts_with('#'))
+        .map(|(i, line)| EpdPosition::parse(line.trim()).map_err(|e| format!("{} line {}: {}", path, i+1, e)))
+        .collect()
+}
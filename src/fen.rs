@@ -0,0 +1,135 @@
+use crate::*;
+
+/// A position in Forsyth-Edwards Notation, reduced to what the board backends keep track of.
+/// The boards only know whether a king moved, so any castling right for a side lets it castle both ways.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Fen {
+    pub board_pos: [usize; 64],
+    pub is_whites_turn: bool,
+    pub has_moved_king_white: bool,
+    pub has_moved_king_black: bool,
+    /// The pawn that just moved two squares, 0 if none. FEN stores the square behind it.
+    pub en_passant: usize,
+    pub halfmove_clock: usize,
+}
+
+impl Fen {
+    pub fn parse(fen: &str) -> Result<Fen, String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() < 4 {
+            return Err(format!("Expected at least 4 fields in FEN, found {}", fields.len()));
+        }
+        let mut board_pos = [0usize; 64];
+        let ranks: Vec<&str> = fields[0].split('/').collect();
+        if ranks.len() != 8 {
+            return Err(format!("Expected 8 ranks in FEN, found {}", ranks.len()));
+        }
+        for (y, rank) in ranks.iter().enumerate() {
+            let mut x = 0;
+            for c in rank.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    x += empty as usize;
+                    continue;
+                }
+                let piece = match util::FEN_PIECES.find(c) {
+                    Some(idx) => idx + 1,
+                    None => return Err(format!("Unknown piece '{}' in FEN", c)),
+                };
+                if x > 7 {
+                    return Err(format!("Rank {} of the FEN is too long", 8-y));
+                }
+                board_pos[x + y*8] = piece;
+                x += 1;
+            }
+            if x != 8 {
+                return Err(format!("Rank {} of the FEN doesn't have 8 squares", 8-y));
+            }
+        }
+        let is_whites_turn = match fields[1] {
+            "w" => true,
+            "b" => false,
+            other => return Err(format!("Unknown side to move '{}' in FEN", other)),
+        };
+        let castling = fields[2];
+        let has_moved_king_white = !(castling.contains('K') || castling.contains('Q')) || board_pos[60] != crate::KING;
+        let has_moved_king_black = !(castling.contains('k') || castling.contains('q')) || board_pos[4] != crate::KING+6;
+        let en_passant = match fields[3] {
+            "-" => 0,
+            target => {
+                let target = util::algebraic_to_pos(target).ok_or(format!("Unknown en passant square '{}' in FEN", target))?;
+                if util::pos_to_xy(target).1 == 5 {target - 8} else {target + 8}
+            }
+        };
+        let halfmove_clock = fields.get(4).and_then(|n| n.parse::<usize>().ok()).unwrap_or(0);
+        Ok(Fen {board_pos, is_whites_turn, has_moved_king_white, has_moved_king_black, en_passant, halfmove_clock})
+    }
+    /// The same position with the colours swapped: the board upside down, white's pieces black and the other side to move.
+    pub fn mirrored(&self) -> Fen {
+        let mut board_pos = [0usize; 64];
+        for pos in 0..64 {
+            let piece = self.board_pos[pos];
+            board_pos[pos ^ 56] = match piece {
+                0 => 0,
+                1..=6 => piece + 6,
+                _ => piece - 6,
+            };
+        }
+        Fen {
+            board_pos,
+            is_whites_turn: !self.is_whites_turn,
+            has_moved_king_white: self.has_moved_king_black,
+            has_moved_king_black: self.has_moved_king_white,
+            en_passant: if self.en_passant == 0 {0} else {self.en_passant ^ 56},
+            halfmove_clock: self.halfmove_clock,
+        }
+    }
+}
+
+/// The boards don't count moves, so the move number is always 1.
+impl std::fmt::Display for Fen {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut fen = String::new();
+        for y in 0..8 {
+            let mut empty = 0;
+            for x in 0..8 {
+                let piece = self.board_pos[x + y*8];
+                if piece == 0 {
+                    empty += 1;
+                    continue;
+                }
+                if empty > 0 {
+                    fen += &empty.to_string();
+                    empty = 0;
+                }
+                fen.push(util::FEN_PIECES.as_bytes()[piece-1] as char);
+            }
+            if empty > 0 {
+                fen += &empty.to_string();
+            }
+            if y < 7 {
+                fen.push('/');
+            }
+        }
+        fen += if self.is_whites_turn {" w "} else {" b "};
+        let mut castling = String::new();
+        for (white, has_moved, rights) in [(true, self.has_moved_king_white, "KQ"), (false, self.has_moved_king_black, "kq")] {
+            let row_offset = if white {7*8} else {0};
+            if has_moved {continue;}
+            for (rook_x, right) in [(7, rights.chars().next().unwrap()), (0, rights.chars().nth(1).unwrap())] {
+                let rook = self.board_pos[rook_x + row_offset];
+                if util::PIECE_TO_COLOURLESS[rook] == crate::ROOK && util::is_piece_white(rook) == white {
+                    castling.push(right);
+                }
+            }
+        }
+        fen += if castling.is_empty() {"-"} else {&castling};
+        fen.push(' ');
+        if self.en_passant == 0 {
+            fen.push('-');
+        } else {
+            let behind = if util::pos_to_xy(self.en_passant).1 == 4 {self.en_passant + 8} else {self.en_passant - 8};
+            fen += &util::pos_to_algebraic(behind);
+        }
+        write!(f, "{} {} 1", fen, self.halfmove_clock)
+    }
+}
@@ -5,6 +5,18 @@ mod util;
 mod cpu;
 mod profiler;
 mod test;
+mod slots;
+mod notation;
+mod clock;
+mod cli;
+mod uci;
+mod config;
+mod match_runner;
+mod epd;
+mod fen;
+mod position;
+mod magic;
+mod pawns;
 
 // 1 king,
 // 2 queen,
@@ -13,12 +25,10 @@ mod test;
 // 5 rook,
 // 6 pawn
 
+use position::Position;
+
+/// The board backend everything runs on, anything implementing `Position` can be swapped in here.
 pub type Board = board_fast::Board;
-pub const DEPTH: usize = 4;
-pub const MAX_DEPTH: usize = 5;
-pub const NORM_EXPLR_DEPTH: usize = 2;
-pub const NORM: i32 = 340;
-pub const RANDOM: bool = true;
 
 pub const NOTHING: usize = 0;
 pub const KING: usize = 1;
@@ -29,5 +39,5 @@ pub const ROOK: usize = 5;
 pub const PAWN: usize = 6;
 
 fn main() {
-    render::main();
+    cli::run();
 }
\ No newline at end of file
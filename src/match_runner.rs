@@ -0,0 +1,297 @@
+use crate::*;
+use std::time::SystemTime;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+pub const DEFAULT_BOOK: &str = "books/openings.txt";
+pub const DEFAULT_PGN: &str = "match.pgn";
+/// Games still going after this many plies are called a draw.
+pub const MAX_PLIES: usize = 400;
+/// Error rates of the SPRT, both for accepting a bad change and rejecting a good one.
+pub const SPRT_ALPHA: f64 = 0.05;
+pub const SPRT_BETA: f64 = 0.05;
+
+/// Who won the game.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    WHITE,
+    BLACK,
+    DRAW,
+}
+
+impl Outcome {
+    pub fn to_pgn(self) -> &'static str {
+        match self {
+            Outcome::WHITE => "1-0",
+            Outcome::BLACK => "0-1",
+            Outcome::DRAW => "1/2-1/2",
+        }
+    }
+}
+
+/// A finished game of the match.
+pub struct MatchGame {
+    pub round: usize,
+    /// Whether `engine` (and not `opponent`) played white.
+    pub engine_white: bool,
+    pub start_board: Board,
+    pub history: Vec<(usize, usize)>,
+    pub outcome: Outcome,
+    pub termination: String,
+}
+
+/// Openings from a book file: one per line, in SAN or as FEN, `#` starting a comment.
+pub fn load_book(path: &str) -> Result<Vec<slots::SavedGame>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let mut openings = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let opening = if line.contains('/') {
+            Board::from_fen(line).map(|board| slots::SavedGame::new(board, vec![]))
+        } else {
+            notation::parse_pgn(line).map(|(board, history)| slots::SavedGame::new(board, history))
+        };
+        openings.push(opening.map_err(|e| format!("{} line {}: {}", path, i+1, e))?);
+    }
+    if openings.is_empty() {
+        return Err(format!("{} has no openings", path));
+    }
+    Ok(openings)
+}
+
+/// The game's result if it is over: mate, stalemate, the fifty move rule, threefold
+/// repetition, insufficient material or running too long.
+pub fn adjudicate(board: &Board, positions: &[u64], halfmove_clock: usize) -> Option<(Outcome, String)> {
+    if !board.has_legal_moves() {
+        if !board.is_in_check(board.is_whites_turn) {
+            return Some((Outcome::DRAW, "stalemate".to_string()));
+        }
+        let outcome = if board.is_whites_turn {Outcome::BLACK} else {Outcome::WHITE};
+        return Some((outcome, "checkmate".to_string()));
+    }
+    if halfmove_clock >= 100 {
+        return Some((Outcome::DRAW, "fifty move rule".to_string()));
+    }
+    if let Some(current) = positions.last() {
+        if positions.iter().filter(|&position| position == current).count() >= 3 {
+            return Some((Outcome::DRAW, "threefold repetition".to_string()));
+        }
+    }
+    if is_insufficient_material(board) {
+        return Some((Outcome::DRAW, "insufficient material".to_string()));
+    }
+    if positions.len() > MAX_PLIES {
+        return Some((Outcome::DRAW, "game too long".to_string()));
+    }
+    None
+}
+
+/// Only kings left, or kings and a single bishop or knight.
+fn is_insufficient_material(board: &Board) -> bool {
+    let pieces = |kinds: &[usize]| kinds.iter().map(|&kind| board.pieces_bitboard[kind] | board.pieces_bitboard[kind+6]).fold(0u64, |all, squares| all | squares);
+    pieces(&[crate::QUEEN, crate::ROOK, crate::PAWN]) == 0 && pieces(&[crate::BISHOP, crate::KNIGHT]).count_ones() <= 1
+}
+
+/// Plays one game from `opening`, `white` and `black` searching within `limits`.
+pub fn play_game(opening: &slots::SavedGame, white: &mut cpu::SearchContext, black: &mut cpu::SearchContext, limits: &cpu::SearchLimits) -> (Vec<(usize, usize)>, Outcome, String) {
+    let mut board = opening.start_board.clone();
+    let mut history = vec![];
+    let mut positions = vec![board.hash()];
+    let mut halfmove_clock = board.check_for_draws_idx; //from the opening's FEN.
+    let mut moves = opening.history.iter().copied();
+    loop {
+        if let Some((outcome, termination)) = adjudicate(&board, &positions, halfmove_clock) {
+            return (history, outcome, termination);
+        }
+        let (from, to) = match moves.next() {
+            Some(book_move) => book_move,
+            None => {
+                let ctx = if board.is_whites_turn {&mut *white} else {&mut *black};
+                let engine_move = cpu::search(&mut board.clone(), limits, ctx).best_move;
+                debug_assert!(board.legal_moves().contains(&engine_move), "Search played the illegal move {:?}", engine_move);
+                engine_move
+            }
+        };
+        let is_pawn_move = util::PIECE_TO_COLOURLESS[board.board_pos[from]] == crate::PAWN;
+        let is_capture = board.move_square(from, to);
+        halfmove_clock = if is_pawn_move || is_capture {0} else {halfmove_clock + 1};
+        history.push((from, to));
+        positions.push(board.hash());
+    }
+}
+
+/// Wins, draws and losses of `engine` against `opponent`.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct MatchStats {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl MatchStats {
+    pub fn add(&mut self, game: &MatchGame) {
+        match (game.outcome, game.engine_white) {
+            (Outcome::DRAW, _) => self.draws += 1,
+            (Outcome::WHITE, true) | (Outcome::BLACK, false) => self.wins += 1,
+            _ => self.losses += 1,
+        }
+    }
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+    /// Points per game, a draw being half a point.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64
+    }
+    /// Variance of the points of a single game.
+    fn variance(&self) -> f64 {
+        let s = self.score();
+        let n = self.games().max(1) as f64;
+        (self.wins as f64 * (1.0-s).powi(2) + self.draws as f64 * (0.5-s).powi(2) + self.losses as f64 * s.powi(2)) / n
+    }
+    /// Elo difference with its 95% confidence margin.
+    pub fn elo(&self) -> (f64, f64) {
+        let s = self.score();
+        let margin = 1.96 * (self.variance() / self.games().max(1) as f64).sqrt();
+        let elo = score_to_elo(s);
+        let high = score_to_elo(s + margin);
+        let low = score_to_elo(s - margin);
+        (elo, (high - low) / 2.0)
+    }
+    /// Log-likelihood ratio of `elo1` against `elo0`, using the normal approximation.
+    pub fn llr(&self, elo0: f64, elo1: f64) -> f64 {
+        let variance = self.variance();
+        if self.games() == 0 || variance == 0.0 {
+            return 0.0;
+        }
+        let s0 = elo_to_score(elo0);
+        let s1 = elo_to_score(elo1);
+        0.5 * self.games() as f64 * (s1 - s0) * (2.0*self.score() - s0 - s1) / variance
+    }
+    /// `Some(true)` once `elo1` is accepted, `Some(false)` once `elo0` is, `None` while undecided.
+    pub fn sprt(&self, elo0: f64, elo1: f64) -> Option<bool> {
+        let llr = self.llr(elo0, elo1);
+        let (lower, upper) = sprt_bounds();
+        if llr >= upper {
+            Some(true)
+        } else if llr <= lower {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn sprt_bounds() -> (f64, f64) {
+    ((SPRT_BETA / (1.0-SPRT_ALPHA)).ln(), ((1.0-SPRT_BETA) / SPRT_ALPHA).ln())
+}
+
+pub fn score_to_elo(score: f64) -> f64 {
+    let score = score.clamp(0.001, 0.999);
+    -400.0 * (1.0/score - 1.0).log10()
+}
+
+pub fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+pub fn print_stats(stats: &MatchStats, sprt: Option<(f64, f64)>) {
+    let (elo, margin) = stats.elo();
+    println!("Games: {}. W/D/L: {}/{}/{}. Score: {:.1}%",
+        stats.games(), stats.wins, stats.draws, stats.losses, stats.score()*100.0);
+    println!("Elo difference: {:.1} +/- {:.1}", elo, margin);
+    if let Some((elo0, elo1)) = sprt {
+        let (lower, upper) = sprt_bounds();
+        let verdict = match stats.sprt(elo0, elo1) {
+            Some(true) => "H1 accepted, the engine is stronger",
+            Some(false) => "H0 accepted, the engine is not stronger",
+            None => "undecided",
+        };
+        println!("SPRT [{}, {}]: LLR {:.2} ({:.2}, {:.2}), {}", elo0, elo1, stats.llr(elo0, elo1), lower, upper, verdict);
+    }
+}
+
+pub fn to_pgn(game: &MatchGame, date: &str) -> String {
+    let (white, black) = if game.engine_white {("engine", "opponent")} else {("opponent", "engine")};
+    let tags = [
+        ("Event", "Engine match".to_string()),
+        ("Site", "?".to_string()),
+        ("Date", date.to_string()),
+        ("Round", game.round.to_string()),
+        ("White", white.to_string()),
+        ("Black", black.to_string()),
+        ("Termination", game.termination.clone()),
+    ];
+    notation::to_pgn(&game.start_board, &game.history, &tags, game.outcome.to_pgn())
+}
+
+/// Plays round `round` of the match, which fixes its opening and colours.
+pub fn play_round(round: usize, openings: &[slots::SavedGame], options: &cli::Options) -> MatchGame {
+    let opening = &openings[(round / 2) % openings.len()];
+    let engine_white = round % 2 == 0;
+    // Seeds follow from the round, not from which thread plays it, so a match can be repeated.
+    let seed = options.seed.wrapping_add(2 * round as u64);
+    let mut engine = cpu::SearchContext::new(options.engine.clone(), seed);
+    let mut opponent = cpu::SearchContext::new(options.opponent.clone(), seed.wrapping_add(1));
+    let (white, black) = if engine_white {(&mut engine, &mut opponent)} else {(&mut opponent, &mut engine)};
+    let limits = options.search_limits();
+    let (history, outcome, termination) = play_game(opening, white, black, &limits);
+    MatchGame {round: round+1, engine_white, start_board: opening.start_board.clone(), history, outcome, termination}
+}
+
+/// Plays `options.engine` against `options.opponent`, each opening of the book twice with colours
+/// swapped, until `options.games` are played or the SPRT is decided. `options.threads` games are
+/// played at once, each on its own thread with its own board and search. Finished games are sent
+/// back to this thread, which keeps the statistics and saves all games as PGN.
+pub fn run(options: &cli::Options) -> Result<(), String> {
+    let openings = load_book(options.book.as_deref().unwrap_or(DEFAULT_BOOK))?;
+    let pgn_path = options.output.clone().unwrap_or(DEFAULT_PGN.to_string());
+    let date = slots::format_date(SystemTime::now())[..10].replace('-', ".");
+    let next_round = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel::<MatchGame>();
+    let mut pgn = String::new();
+    let mut stats = MatchStats::default();
+    let mut result = Ok(());
+    println!("Seed: {}", options.seed);
+    thread::scope(|scope| {
+        for _ in 0..options.threads.max(1) {
+            let sender = sender.clone();
+            let (next_round, stop, openings) = (&next_round, &stop, &openings);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let round = next_round.fetch_add(1, Ordering::Relaxed);
+                    if round >= options.games || sender.send(play_round(round, openings, options)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender); //the loop below ends once every thread is done.
+
+        for game in receiver {
+            stats.add(&game);
+            println!("Game {} ({}/{}): {} ({}), engine played {}.", game.round, stats.games(), options.games,
+                game.outcome.to_pgn(), game.termination, if game.engine_white {"white"} else {"black"});
+            pgn += &to_pgn(&game, &date);
+            pgn.push('\n');
+            if let Err(e) = std::fs::write(&pgn_path, &pgn) {
+                result = Err(format!("Failed to write {}: {}", pgn_path, e));
+                stop.store(true, Ordering::Relaxed);
+            }
+            if let Some((elo0, elo1)) = options.sprt {
+                if stats.sprt(elo0, elo1).is_some() {
+                    stop.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    });
+    result?;
+    print_stats(&stats, options.sprt);
+    println!("Saved the games to {}", pgn_path);
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use crate::*;
+
+pub const PIECE_LETTERS: [&str; 7] = ["", "K", "Q", "B", "N", "R", ""];
+
+/// Standard algebraic notation of a move, `board` being the position before it is played.
+pub fn move_to_san<P: Position>(board: &P, from: usize, to: usize) -> String {
+    let piece = board.piece_at(from);
+    let white = util::is_piece_white(piece);
+    let colourless = util::PIECE_TO_COLOURLESS[piece];
+    let (from_x, from_y) = util::pos_to_xy(from);
+    let (to_x, to_y) = util::pos_to_xy(to);
+    let is_capture = board.piece_at(to) != 0;
+
+    let mut san = if colourless == KING && from_x.abs_diff(to_x) == 2 {
+        if to_x > from_x {"O-O".to_string()} else {"O-O-O".to_string()}
+    } else if colourless == PAWN {
+        let mut san = String::new();
+        if is_capture || from_x != to_x {
+            san.push((b'a' + from_x as u8) as char);
+            san.push('x');
+        }
+        san += &util::pos_to_algebraic(to);
+        if to_y % 7 == 0 {
+            san += "=Q"; //pawns always promote to a queen.
+        }
+        san
+    } else {
+        // Other pieces of the same kind that could go to the same square.
+        let rivals: Vec<usize> = util::BitIter::new(board.pieces(white))
+            .filter(|&pos| pos != from && board.piece_at(pos) == piece)
+            .filter(|&pos| board.moveable_squares(pos) & (1<<to) != 0 && board.is_legal_move((pos, to)))
+            .collect();
+        let mut san = PIECE_LETTERS[colourless].to_string();
+        if !rivals.is_empty() {
+            let same_file = rivals.iter().any(|&pos| util::pos_to_xy(pos).0 == from_x);
+            let same_rank = rivals.iter().any(|&pos| util::pos_to_xy(pos).1 == from_y);
+            if !same_file {
+                san.push((b'a' + from_x as u8) as char);
+            } else if !same_rank {
+                san += &(8-from_y).to_string();
+            } else {
+                san += &util::pos_to_algebraic(from);
+            }
+        }
+        if is_capture {
+            san.push('x');
+        }
+        san += &util::pos_to_algebraic(to);
+        san
+    };
+
+    let mut after = board.clone();
+    after.make_move((from, to));
+    if after.is_in_check(after.is_whites_turn()) {
+        san.push(if after.has_legal_moves() {'+'} else {'#'});
+    }
+    san
+}
+
+/// Finds the legal move written as `san`, ignoring check marks and annotations.
+pub fn san_to_move<P: Position>(board: &P, san: &str) -> Option<(usize, usize)> {
+    let san = san.trim_end_matches(['+', '#', '!', '?']).replace('0', "O");
+    board.legal_moves().into_iter()
+        .find(|&(from, to)| move_to_san(board, from, to).trim_end_matches(['+', '#']) == san)
+}
+
+/// Long algebraic notation as used by UCI, like `e2e4` or `e7e8q`.
+pub fn move_to_uci<P: Position>(board: &P, from: usize, to: usize) -> String {
+    let mut text = util::pos_to_algebraic(from) + &util::pos_to_algebraic(to);
+    if util::PIECE_TO_COLOURLESS[board.piece_at(from)] == PAWN && util::pos_to_xy(to).1 % 7 == 0 {
+        text.push('q');
+    }
+    text
+}
+
+pub fn uci_to_move<P: Position>(board: &P, text: &str) -> Option<(usize, usize)> {
+    if text.len() < 4 || !text.is_char_boundary(2) || !text.is_char_boundary(4) {
+        return None;
+    }
+    let from = util::algebraic_to_pos(&text[0..2])?;
+    let to = util::algebraic_to_pos(&text[2..4])?;
+    if !matches!(&text[4..], "" | "q") || !board.legal_moves().contains(&(from, to)) {
+        return None; //pawns can only promote to a queen.
+    }
+    Some((from, to))
+}
+
+/// Reads the first game of a PGN file, returning its start position and moves.
+pub fn parse_pgn<P: Position>(pgn: &str) -> Result<(P, Vec<(usize, usize)>), String> {
+    let mut board = P::start_position();
+    let mut movetext = String::new();
+    for line in pgn.lines() {
+        let line = line.trim();
+        if let Some(tag) = line.strip_prefix('[') {
+            if let Some(fen) = tag.strip_prefix("FEN \"").and_then(|fen| fen.strip_suffix("\"]")) {
+                board = P::from_fen(fen)?;
+            }
+            if !movetext.trim().is_empty() {
+                break; //the next game starts.
+            }
+        } else if !line.starts_with('%') {
+            movetext += line;
+            movetext.push(' ');
+        }
+    }
+
+    let start_board = board.clone();
+    let mut history = vec![];
+    let mut text = String::new();
+    let mut comment_depth = 0;
+    let mut variation_depth = 0;
+    for c in movetext.chars() {
+        match c {
+            '{' => comment_depth += 1,
+            '}' => comment_depth -= 1,
+            '(' if comment_depth == 0 => variation_depth += 1,
+            ')' if comment_depth == 0 => variation_depth -= 1,
+            _ if comment_depth == 0 && variation_depth == 0 => text.push(c),
+            _ => {},
+        }
+    }
+    for token in text.split_whitespace() {
+        if matches!(token, "*" | "1-0" | "0-1" | "1/2-1/2") {
+            break;
+        }
+        let token = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+        if token.is_empty() || token.starts_with('$') {
+            continue;
+        }
+        match san_to_move(&board, token) {
+            Some((from, to)) => {
+                board.make_move((from, to));
+                history.push((from, to));
+            }
+            None => return Err(format!("Illegal or unsupported move {} after {} moves", token, history.len())),
+        }
+    }
+    Ok((start_board, history))
+}
+
+/// A PGN game of `history` played from `start_board`, `tags` coming before the Seven Tag Roster defaults.
+pub fn to_pgn<P: Position>(start_board: &P, history: &[(usize, usize)], tags: &[(&str, String)], result: &str) -> String {
+    let mut pgn = String::new();
+    for (name, value) in tags {
+        pgn += &format!("[{} \"{}\"]\n", name, value);
+    }
+    for name in ["Event", "Site", "Date", "Round", "White", "Black"] {
+        if !tags.iter().any(|(tag, _)| *tag == name) {
+            pgn += &format!("[{} \"?\"]\n", name);
+        }
+    }
+    pgn += &format!("[Result \"{}\"]\n", result);
+    let is_custom_start = start_board.to_fen() != P::start_position().to_fen();
+    if is_custom_start {
+        pgn += &format!("[SetUp \"1\"]\n[FEN \"{}\"]\n", start_board.to_fen());
+    }
+    pgn.push('\n');
+
+    let mut board = start_board.clone();
+    let mut line = String::new();
+    let mut move_number = 1;
+    for (i, &(from, to)) in history.iter().enumerate() {
+        let mut text = String::new();
+        if board.is_whites_turn() {
+            text += &format!("{}. ", move_number);
+        } else if i == 0 {
+            text += &format!("{}... ", move_number);
+        }
+        text += &move_to_san(&board, from, to);
+        if !board.is_whites_turn() {
+            move_number += 1;
+        }
+        board.make_move((from, to));
+        if line.len() + text.len() >= 80 {
+            pgn += line.trim_end();
+            pgn.push('\n');
+            line.clear();
+        }
+        line += &text;
+        line.push(' ');
+    }
+    pgn += &line;
+    pgn += result;
+    pgn.push('\n');
+    pgn
+}
@@ -0,0 +1,188 @@
+use crate::*;
+use crate::position::Position;
+
+/// Number of entries in a `PawnTable`, a power of two so the key can be masked into an index.
+pub const PAWN_TABLE_SIZE: usize = 1 << 14;
+
+// Midgame and endgame values of the pawn structure terms, per pawn.
+const DOUBLED: (i32, i32) = (-10, -20);
+const ISOLATED: (i32, i32) = (-10, -15);
+const BACKWARD: (i32, i32) = (-8, -10);
+const CONNECTED: (i32, i32) = (5, 8);
+/// For having more pawns than the other side on a wing, which tends to make a passed pawn later on.
+const MAJORITY: (i32, i32) = (0, 15);
+/// For a passed pawn by how far it has come, counted in ranks from its own side. Half of it when something stands in its way.
+const PASSED: [(i32, i32); 8] = [(0, 0), (0, 5), (5, 10), (10, 20), (20, 35), (35, 60), (60, 100), (0, 0)];
+
+const FILE_A: u64 = 0x0101010101010101;
+const QUEENSIDE: u64 = FILE_A * 0x0f;
+const KINGSIDE: u64 = FILE_A * 0xf0;
+
+/// Random numbers for a white and a black pawn on every square, xor-ed together into `Position::pawn_key`.
+const KEYS: [[u64; 64]; 2] = zobrist_keys();
+
+const fn zobrist_keys() -> [[u64; 64]; 2] {
+    let mut keys = [[0u64; 64]; 2];
+    let mut state: u64 = 0x2545f4914f6cdd1d;
+    let mut i = 0;
+    while i < 128 {
+        // splitmix64, good enough and it runs at compile time.
+        state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        keys[i / 64][i % 64] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+/// What `piece` on `pos` adds to the pawn key, nothing for pieces other than pawns.
+pub fn key(piece: usize, pos: usize) -> u64 {
+    match piece {
+        crate::PAWN => KEYS[0][pos],
+        p if p == crate::PAWN+6 => KEYS[1][pos],
+        _ => 0,
+    }
+}
+
+/// The pawn key of a whole board.
+pub fn key_of(board_pos: &[usize; 64]) -> u64 {
+    (0..64).fold(0, |pawn_key, pos| pawn_key ^ key(board_pos[pos], pos))
+}
+
+/// Every square on the ranks in front of `pos`, seen from `white`.
+fn ranks_ahead(pos: usize, white: bool) -> u64 {
+    let y = util::pos_to_xy(pos).1;
+    if white {(1u64 << (8*y)) - 1} else if y == 7 {0} else {!((1u64 << (8*(y+1))) - 1)}
+}
+
+/// The squares in front of `pos` on its file, seen from `white`.
+fn ahead(pos: usize, white: bool) -> u64 {
+    (FILE_A << util::pos_to_xy(pos).0) & ranks_ahead(pos, white)
+}
+
+fn adjacent_files(pos: usize) -> u64 {
+    let x = util::pos_to_xy(pos).0;
+    (if x > 0 {FILE_A << (x-1)} else {0}) | (if x < 7 {FILE_A << (x+1)} else {0})
+}
+
+fn pawn_attacks(pawns: u64, white: bool) -> u64 {
+    let not_a = !FILE_A;
+    let not_h = !(FILE_A << 7);
+    if white {
+        ((pawns & not_a) >> 9) | ((pawns & not_h) >> 7)
+    } else {
+        ((pawns & not_a) << 7) | ((pawns & not_h) << 9)
+    }
+}
+
+/// The bonus of a passed pawn of `white` on `pos`, positive for either colour.
+fn passed_bonus(pos: usize, white: bool) -> (i32, i32) {
+    let y = util::pos_to_xy(pos).1;
+    PASSED[if white {7-y} else {y}]
+}
+
+/// Everything about the pawns that only depends on the pawns.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct PawnStructure {
+    /// Midgame and endgame score, positive when white's pawns are better. Passed pawns count as unblocked.
+    pub score: (i32, i32),
+    /// The passed pawns of both sides.
+    pub passed: u64,
+}
+
+impl PawnStructure {
+    pub fn new(white_pawns: u64, black_pawns: u64) -> PawnStructure {
+        let (white_score, white_passed) = side(white_pawns, black_pawns, true);
+        let (black_score, black_passed) = side(black_pawns, white_pawns, false);
+        PawnStructure {
+            score: (white_score.0 - black_score.0, white_score.1 - black_score.1),
+            passed: white_passed | black_passed,
+        }
+    }
+}
+
+/// The score of the pawns `own` of `white` against `enemy` and which of them are passed.
+fn side(own: u64, enemy: u64, white: bool) -> ((i32, i32), u64) {
+    let mut score = (0, 0);
+    let mut add = |term: (i32, i32)| {
+        score.0 += term.0;
+        score.1 += term.1;
+    };
+    let mut passed = 0u64;
+    let enemy_attacks = pawn_attacks(enemy, !white);
+    // A pawn is defended from the squares an enemy pawn on it would attack.
+    let supporting: &[u64; 64] = if white {&util::BLACK_PAWN_CAPTURES} else {&util::WHITE_PAWN_CAPTURES};
+    for pos in util::BitIter::new(own) {
+        let neighbours = own & adjacent_files(pos);
+        let is_doubled = own & ahead(pos, white) != 0;
+        if is_doubled {
+            add(DOUBLED);
+        }
+        if neighbours == 0 {
+            add(ISOLATED);
+        } else {
+            let rank = 0xffu64 << (8*util::pos_to_xy(pos).1);
+            if own & supporting[pos] != 0 || neighbours & rank != 0 {
+                add(CONNECTED);
+            }
+            // Backward: every neighbour is further up already and an enemy pawn guards the square in front.
+            let stop = if white {pos.wrapping_sub(8)} else {pos+8};
+            if neighbours & !ranks_ahead(pos, white) == 0 && stop < 64 && enemy_attacks & (1 << stop) != 0 {
+                add(BACKWARD);
+            }
+        }
+        let span = ahead(pos, white) | (adjacent_files(pos) & ranks_ahead(pos, white));
+        if !is_doubled && enemy & span == 0 {
+            passed |= 1 << pos;
+            add(passed_bonus(pos, white));
+        }
+    }
+    for wing in [QUEENSIDE, KINGSIDE] {
+        if (own & wing).count_ones() > (enemy & wing).count_ones() {
+            add(MAJORITY);
+        }
+    }
+    (score, passed)
+}
+
+/// Recently seen pawn structures by `Position::pawn_key`. Positions in a search mostly share their pawns,
+/// so most lookups find the structure already worked out.
+pub struct PawnTable {
+    entries: Vec<(u64, PawnStructure)>,
+}
+
+impl PawnTable {
+    /// An empty table, every slot starts out as the pawnless structure, whose key is 0.
+    pub fn new() -> PawnTable {
+        PawnTable {entries: vec![(0, PawnStructure::default()); PAWN_TABLE_SIZE]}
+    }
+    pub fn get(&mut self, pawn_key: u64, white_pawns: u64, black_pawns: u64) -> PawnStructure {
+        let entry = &mut self.entries[pawn_key as usize & (PAWN_TABLE_SIZE-1)];
+        if entry.0 != pawn_key {
+            *entry = (pawn_key, PawnStructure::new(white_pawns, black_pawns));
+        }
+        entry.1
+    }
+}
+
+/// The pawn structure score of `board` in centipawns, positive when white is better.
+/// Passed pawns with something in front of them get half their bonus.
+pub fn evaluate<P: Position>(board: &P, pawn_table: &mut PawnTable) -> i32 {
+    let white_pawns = board.squares_of(crate::PAWN);
+    let structure = pawn_table.get(board.pawn_key(), white_pawns, board.squares_of(crate::PAWN+6));
+    let (mut midgame, mut endgame) = structure.score;
+    let occupied = board.pieces(true) | board.pieces(false);
+    for pos in util::BitIter::new(structure.passed) {
+        let white = white_pawns & (1 << pos) != 0;
+        if ahead(pos, white) & occupied != 0 {
+            let bonus = passed_bonus(pos, white);
+            let sign = if white {1} else {-1};
+            midgame -= sign * bonus.0 / 2;
+            endgame -= sign * bonus.1 / 2;
+        }
+    }
+    let phase = board.phase().min(util::MAX_PHASE);
+    (midgame*phase + endgame*(util::MAX_PHASE-phase)) / util::MAX_PHASE
+}
@@ -0,0 +1,156 @@
+use crate::*;
+
+/// A move as the squares it goes from and to.
+pub type Move = (usize, usize);
+
+/// What every board backend offers, so the search and the window can be written once
+/// and backends can be swapped, benchmarked and checked against each other.
+/// Only the first block has to be written for a new backend, the rest follows from it.
+pub trait Position: Clone {
+    /// What `unmake_move` needs to take a move back.
+    type Undo;
+
+    fn start_position() -> Self;
+    fn from_fen(fen: &str) -> Result<Self, String>;
+    fn to_fen(&self) -> String;
+    fn is_whites_turn(&self) -> bool;
+    fn piece_at(&self, pos: usize) -> usize;
+    /// Squares the piece on `pos` can move to, own king safety not considered.
+    fn moveable_squares(&self, pos: usize) -> u64;
+    fn make_move(&mut self, mv: Move) -> Self::Undo;
+    fn unmake_move(&mut self, undo: Self::Undo);
+    /// Score in centipawns, positive when white is better.
+    fn evaluate(&self) -> i32;
+
+    /// The same for positions that count as repeated: same pieces, side to move, castling and en passant.
+    fn hash(&self) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for field in self.to_fen().split_whitespace().take(4) {
+            std::hash::Hash::hash(field, &mut hasher);
+        }
+        std::hash::Hasher::finish(&hasher)
+    }
+    /// Multiplies what `evaluate` gives by `factor`, for this position and the ones moved to from it,
+    /// and returns what `restore_score` needs to take it back. Backends that work the score out anew don't scale.
+    fn scale_score(&mut self, _factor: f32) -> i32 {
+        0
+    }
+    fn restore_score(&mut self, _scaling: i32) {}
+    /// The squares holding `piece`.
+    fn squares_of(&self, piece: usize) -> u64 {
+        (0..64usize).filter(|&pos| self.piece_at(pos) == piece).fold(0u64, |squares, pos| squares | 1<<pos)
+    }
+    /// How much of the pieces are left, from `util::MAX_PHASE` at the start down to 0 with only kings and pawns.
+    fn phase(&self) -> i32 {
+        (0..64).map(|pos| util::PHASE_WEIGHTS[self.piece_at(pos)]).sum()
+    }
+    /// The same for positions with the same pawns, see `pawns::key`.
+    fn pawn_key(&self) -> u64 {
+        (0..64).fold(0, |pawn_key, pos| pawn_key ^ pawns::key(self.piece_at(pos), pos))
+    }
+    /// `evaluate` with the pawn structure added, which `pawn_table` keeps so it is only worked out once per set of pawns.
+    fn evaluate_with_pawns(&self, pawn_table: &mut pawns::PawnTable) -> i32 {
+        self.evaluate() + pawns::evaluate(self, pawn_table)
+    }
+    /// The squares holding pieces of `white`.
+    fn pieces(&self, white: bool) -> u64 {
+        (0..64usize)
+            .filter(|&pos| self.piece_at(pos) != 0 && util::is_piece_white(self.piece_at(pos)) == white)
+            .fold(0u64, |pieces, pos| pieces | 1<<pos)
+    }
+    fn find_king(&self, white: bool) -> Option<usize> {
+        let king = if white {crate::KING} else {crate::KING+6};
+        util::BitIter::new(self.pieces(white)).find(|&pos| self.piece_at(pos) == king)
+    }
+    /// Every square a piece of `white` could capture on, pawn pushes and castling left out.
+    fn attacked_squares(&self, white: bool) -> u64 {
+        let colour_pawn_captures: &[u64; 64] = if white {&*util::WHITE_PAWN_CAPTURES} else {&*util::BLACK_PAWN_CAPTURES};
+        util::BitIter::new(self.pieces(white)).fold(0u64, |attacked, pos| {
+            attacked | match util::PIECE_TO_COLOURLESS[self.piece_at(pos)] {
+                crate::KING => util::KING_MOVES[pos],
+                crate::PAWN => colour_pawn_captures[pos],
+                _ => self.moveable_squares(pos),
+            }
+        })
+    }
+    fn is_in_check(&self, white: bool) -> bool {
+        match self.find_king(white) {
+            Some(king_pos) => self.attacked_squares(!white) & (1<<king_pos) != 0,
+            None => false,
+        }
+    }
+    /// The pieces giving check to the side to move.
+    fn checkers(&self) -> u64 {
+        let white = self.is_whites_turn();
+        let king_pos = match self.find_king(white) {
+            Some(pos) => pos,
+            None => return 0,
+        };
+        let colour_pawn_captures: &[u64; 64] = if white {&*util::BLACK_PAWN_CAPTURES} else {&*util::WHITE_PAWN_CAPTURES};
+        util::BitIter::new(self.pieces(!white)).filter(|&pos| {
+            let attacks = match util::PIECE_TO_COLOURLESS[self.piece_at(pos)] {
+                crate::KING => util::KING_MOVES[pos],
+                crate::PAWN => colour_pawn_captures[pos],
+                _ => self.moveable_squares(pos),
+            };
+            attacks & (1<<king_pos) != 0
+        }).fold(0u64, |checkers, pos| checkers | 1<<pos)
+    }
+    /// Pieces of the side to move that can't leave the line between their king and an enemy slider.
+    /// Walks out from the king one square at a time, for backends to be checked against.
+    fn pinned(&self) -> u64 {
+        let white = self.is_whites_turn();
+        let king_pos = match self.find_king(white) {
+            Some(pos) => pos,
+            None => return 0,
+        };
+        let (king_x, king_y) = ((king_pos % 8) as isize, (king_pos / 8) as isize);
+        let mut pinned = 0u64;
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (-1, -1), (-1, 1), (1, -1)] {
+            let sniper = if dx == 0 || dy == 0 {crate::ROOK} else {crate::BISHOP};
+            let mut own_piece = None;
+            let (mut x, mut y) = (king_x + dx, king_y + dy);
+            while (0..8).contains(&x) && (0..8).contains(&y) {
+                let pos = (x + y*8) as usize;
+                let piece = self.piece_at(pos);
+                if piece != 0 {
+                    match own_piece {
+                        None if util::is_piece_white(piece) == white => own_piece = Some(pos),
+                        Some(own_pos) if util::is_piece_white(piece) != white
+                            && [sniper, crate::QUEEN].contains(&util::PIECE_TO_COLOURLESS[piece]) => pinned |= 1<<own_pos,
+                        _ => {}
+                    }
+                    if own_piece != Some(pos) {
+                        break;
+                    }
+                }
+                x += dx;
+                y += dy;
+            }
+        }
+        pinned
+    }
+    /// Whether the exchange `mv` starts wins at least `threshold`, see `board_fast::Board::see`.
+    /// Backends without static exchange evaluation say yes, so nothing gets pruned on them.
+    fn see_ge(&self, _mv: Move, _threshold: i32) -> bool {
+        true
+    }
+    /// Whether the move doesn't leave the own king in check.
+    fn is_legal_move(&self, mv: Move) -> bool {
+        let white = util::is_piece_white(self.piece_at(mv.0));
+        let mut board = self.clone();
+        board.make_move(mv);
+        !board.is_in_check(white)
+    }
+    fn pseudo_legal_moves(&self) -> Vec<Move> {
+        util::BitIter::new(self.pieces(self.is_whites_turn()))
+            .flat_map(|from| util::BitIter::new(self.moveable_squares(from)).map(move |to| (from, to)))
+            .collect()
+    }
+    fn legal_moves(&self) -> Vec<Move> {
+        self.pseudo_legal_moves().into_iter().filter(|&mv| self.is_legal_move(mv)).collect()
+    }
+    fn has_legal_moves(&self) -> bool {
+        self.pseudo_legal_moves().into_iter().any(|mv| self.is_legal_move(mv))
+    }
+}
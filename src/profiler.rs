@@ -1,9 +1,7 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 use hashbrown::HashMap;
 
-extern crate lazy_static;
-use lazy_static::lazy_static;
-use std::sync::Mutex;
+use std::cell::RefCell;
 
 const ON: bool = false;
 
@@ -22,23 +20,25 @@ impl Profiler {
 
 type Dictionary = HashMap<String, u128>;
 
-lazy_static! {
-    static ref TIME_TABLE: Mutex<Dictionary> = Mutex::new(HashMap::new());
+// Every thread times itself, so games played at once by the match runner don't wait on each other.
+thread_local! {
+    static TIME_TABLE: RefCell<Dictionary> = RefCell::new(HashMap::new());
 }
 
 fn add_time(name: &String, elapsed: u128) {
     if ON {
-        let mut table = TIME_TABLE.lock().unwrap();
-        *table.get_mut(name).unwrap() += elapsed;
+        TIME_TABLE.with(|table| *table.borrow_mut().get_mut(name).unwrap() += elapsed);
     }
 }
 
 pub fn start_timing(name: &str) -> Profiler {
     if ON {
-        let mut table = TIME_TABLE.lock().unwrap();
-        if !table.contains_key(name) {
-            table.insert(name.to_string(), 0);
-        }
+        TIME_TABLE.with(|table| {
+            let mut table = table.borrow_mut();
+            if !table.contains_key(name) {
+                table.insert(name.to_string(), 0);
+            }
+        });
         Profiler {name: name.to_string(), time_started: SystemTime::now()}
     } else {
         Profiler {name: name.to_string(), time_started: SystemTime::UNIX_EPOCH}
@@ -46,11 +46,13 @@ pub fn start_timing(name: &str) -> Profiler {
 }
 
 pub fn reset() {
-    TIME_TABLE.lock().unwrap().clear();
+    TIME_TABLE.with(|table| table.borrow_mut().clear());
 }
+/// Prints and clears what the calling thread timed.
 pub fn print() {
-    let mut table = TIME_TABLE.lock().unwrap();
-    for (name, elapsed) in table.drain() {
-        println!("{}: Took {}ms", name, elapsed/1000_000);
-    }
+    TIME_TABLE.with(|table| {
+        for (name, elapsed) in table.borrow_mut().drain() {
+            println!("{}: Took {}ms", name, elapsed/1000_000);
+        }
+    });
 }
\ No newline at end of file
@@ -2,6 +2,7 @@ use ggez::conf;
 use ggez::graphics::StrokeOptions;
 use ggez::mint::{self, Vector2};
 use ggez::event::MouseButton;
+use ggez::input::keyboard::{KeyCode, KeyInput, KeyMods};
 use ggez::input::mouse::button_pressed;
 use ggez::filesystem;
 use ggez::GameError;
@@ -22,34 +23,244 @@ use std::fs::File;
 use std::io::prelude::*;
 
 pub const BORDER_SIZE: f32 = 10.0;
-pub const DX: f32 = BORDER_SIZE+40.0+BORDER_SIZE+20.0+BORDER_SIZE;
+pub const SIDE_PANEL_WIDTH: f32 = 160.0;
+pub const DX: f32 = BORDER_SIZE+SIDE_PANEL_WIDTH+BORDER_SIZE+20.0+BORDER_SIZE;
 pub const DY: f32 = 0.0+BORDER_SIZE;
 pub const WINDOW_WIDTH: f32 = 600.0 + DX+BORDER_SIZE;
 pub const WINDOW_HEIGHT: f32 = 600.0 + DY + BORDER_SIZE;
 pub const SQUARE_SIZE: f32 = (WINDOW_WIDTH-DX-BORDER_SIZE) / 8.0;
 
-#[derive(PartialEq)]
+pub const BUTTON_WIDTH: f32 = (SIDE_PANEL_WIDTH-BORDER_SIZE) / 2.0;
+pub const BUTTON_HEIGHT: f32 = 20.0;
+pub const BUTTON_ROWS: usize = 4;
+const fn side_button(col: usize, row: usize) -> graphics::Rect {
+    graphics::Rect::new(
+        BORDER_SIZE + col as f32*(BUTTON_WIDTH+BORDER_SIZE),
+        BORDER_SIZE + row as f32*(BUTTON_HEIGHT+BORDER_SIZE),
+        BUTTON_WIDTH,
+        BUTTON_HEIGHT,
+    )
+}
+pub const SAVE_BUTTON: graphics::Rect = side_button(0, 0);
+pub const LOAD_BUTTON: graphics::Rect = side_button(1, 0);
+pub const UNDO_BUTTON: graphics::Rect = side_button(0, 1);
+pub const REDO_BUTTON: graphics::Rect = side_button(1, 1);
+pub const BRANCH_BUTTON: graphics::Rect = side_button(0, 2);
+pub const FLIP_BUTTON: graphics::Rect = side_button(1, 2);
+pub const ATTACKED_BUTTON: graphics::Rect = side_button(0, 3);
+pub const NEW_GAME_BUTTON: graphics::Rect = side_button(1, 3);
+
+pub const CLOCK_Y: f32 = BORDER_SIZE + BUTTON_ROWS as f32*(BUTTON_HEIGHT+BORDER_SIZE);
+pub const CLOCK_ROW_HEIGHT: f32 = 20.0;
+pub const MATERIAL_Y: f32 = CLOCK_Y + 3.0*CLOCK_ROW_HEIGHT + BORDER_SIZE;
+pub const MATERIAL_ROW_HEIGHT: f32 = 20.0;
+pub const CAPTURED_PIECE_SIZE: f32 = 18.0;
+pub const CAPTURED_PIECE_STEP: f32 = 9.0;
+pub const MOVE_LIST_Y: f32 = MATERIAL_Y + 2.0*MATERIAL_ROW_HEIGHT + BORDER_SIZE;
+pub const MOVE_LIST: graphics::Rect = graphics::Rect::new(BORDER_SIZE, MOVE_LIST_Y, SIDE_PANEL_WIDTH, WINDOW_HEIGHT-MOVE_LIST_Y-BORDER_SIZE);
+pub const MOVE_ROW_HEIGHT: f32 = 20.0;
+pub const MOVE_LIST_ROWS: usize = (MOVE_LIST.h / MOVE_ROW_HEIGHT) as usize;
+pub const MOVE_NUMBER_WIDTH: f32 = 34.0;
+pub const MOVE_WIDTH: f32 = (SIDE_PANEL_WIDTH-MOVE_NUMBER_WIDTH) / 2.0;
+
+pub const PANEL: graphics::Rect = graphics::Rect::new(DX+SQUARE_SIZE/2.0, DY+SQUARE_SIZE/2.0, 7.0*SQUARE_SIZE, 7.0*SQUARE_SIZE);
+pub const PANEL_ROW_HEIGHT: f32 = 28.0;
+pub const PANEL_LIST_Y: f32 = PANEL.y + 2.0*BORDER_SIZE + 2.0*PANEL_ROW_HEIGHT;
+pub const PANEL_VISIBLE_ROWS: usize = ((PANEL.h - (PANEL_LIST_Y-PANEL.y) - BORDER_SIZE) / PANEL_ROW_HEIGHT) as usize;
+pub const PANEL_NAME_FIELD: graphics::Rect = graphics::Rect::new(PANEL.x+BORDER_SIZE+50.0, PANEL.y+BORDER_SIZE+PANEL_ROW_HEIGHT, PANEL.w-4.0*BORDER_SIZE-50.0-120.0, 22.0);
+pub const PANEL_SAVE_BUTTON: graphics::Rect = graphics::Rect::new(PANEL.x+PANEL.w-2.0*BORDER_SIZE-120.0, PANEL.y+BORDER_SIZE+PANEL_ROW_HEIGHT, 60.0, 22.0);
+pub const PANEL_CLOSE_BUTTON: graphics::Rect = graphics::Rect::new(PANEL.x+PANEL.w-BORDER_SIZE-60.0, PANEL.y+BORDER_SIZE+PANEL_ROW_HEIGHT, 60.0, 22.0);
+
+#[derive(PartialEq, Clone, Copy)]
 pub enum Player {
     HUMAN,
     BOT,
 }
-pub struct Game {
-    pub board: Board, pub square_light: graphics::Mesh,
+/// All colours used to draw the game.
+pub struct Theme {
+    pub background: Color,
+    pub light_square: Color,
+    pub dark_square: Color,
+    pub selected: Color,
+    pub move_target: Color,
+    pub capture_target: Color,
+    pub last_move: Color,
+    pub check: Color,
+    pub attacked: Color,
+    pub button: Color,
+    pub panel: Color,
+    pub move_list_highlight: Color,
+}
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            background: Color::from([0.1, 0.2, 0.3, 1.0]),
+            light_square: Color::WHITE,
+            dark_square: Color::from_rgb(96, 65, 58),
+            selected: Color::BLACK,
+            move_target: Color::BLACK,
+            capture_target: Color::from_rgb(200, 30, 30),
+            last_move: Color::from([0.8, 0.8, 0.2, 0.45]),
+            check: Color::from([0.9, 0.0, 0.0, 0.6]),
+            attacked: Color::from([0.9, 0.3, 0.0, 0.35]),
+            button: Color::BLACK,
+            panel: Color::from([0.1, 0.2, 0.3, 0.95]),
+            move_list_highlight: Color::from_rgb(96, 65, 58),
+        }
+    }
+}
+
+/// Bot strengths to pick from, as the time one iteration of the search may take before it settles.
+pub const BOT_STRENGTHS: [(&str, u64); 5] = [("Beginner", 5), ("Easy", 30), ("Normal", 100), ("Hard", 300), ("Master", 1000)];
+pub const DEFAULT_BOT_STRENGTH: usize = 2;
+/// Time controls to pick from, `None` being a game without clocks.
+pub const TIME_CONTROLS: [Option<&str>; 7] = [None, Some("1+0"), Some("3+2"), Some("5+3"), Some("10+5"), Some("15+10"), Some("40/90+30")];
+
+/// What happens when a move is made while looking at an earlier position.
+#[derive(PartialEq, Clone, Copy)]
+pub enum BranchMode {
+    /// The moves after the viewed position are thrown away and the game continues from there.
+    BRANCH,
+    /// Moves can only be made on the current position.
+    BLOCK,
+}
+pub struct Game<P: Position = Board> {
+    pub board: P, pub square_light: graphics::Mesh,
     pub square_dark: graphics::Mesh,
     pub square_highlight: graphics::Mesh,
     pub square_moveable: graphics::Mesh,
+    pub square_capturable: graphics::Mesh,
+    pub square_last_move: graphics::Mesh,
+    pub square_check: graphics::Mesh,
+    pub square_attacked: graphics::Mesh,
+    pub theme: Theme,
+    /// Shades every square the side not to move attacks.
+    pub show_attacked: bool,
     pub pieces: [graphics::Image; 12],
 
     pub selected_square: Option<usize>,
     pub player_white: Player,
     pub player_black: Player,
     pub history: Vec<(usize, usize)>,
+    /// Why the game ended, no more moves are made once it is set.
+    pub game_over: Option<String>,
+    pub slot_panel: Option<SlotPanel>,
+    pub new_game_panel: Option<NewGamePanel>,
+    pub redo: Vec<(usize, usize)>,
+    pub view_ply: Option<usize>,
+    pub view_board: Option<P>,
+    pub branch_mode: BranchMode,
+    /// SAN of every move in `history`.
+    pub move_names: Vec<String>,
+    /// The piece taken by every move in `history`, 0 when nothing was.
+    pub captures: Vec<usize>,
+    /// First visible row of the move list once the user scrolled it, otherwise it follows the viewed move.
+    pub move_list_scroll: Option<usize>,
+    /// The square of the piece that is being dragged with the mouse.
+    pub dragging: Option<usize>,
+    /// Draws the board with black at the bottom.
+    pub flipped: bool,
+    /// None for games without a time control.
+    pub clock: Option<clock::Clock>,
+    /// The clock as it was when each position of `history` was reached, the start first.
+    /// Kept past the end of `history` after an undo, so a redo gets its time back too.
+    pub clocks: Vec<Option<clock::Clock>>,
+    /// Position the game started from, `history` is played from here.
+    pub start_board: P,
+    /// How long one iteration of the bot's search may take when there's no clock.
+    pub bot_think_ms: u64,
+    pub search_context: cpu::SearchContext,
+}
+
+/// The save slot chooser that is drawn over the board.
+pub struct SlotPanel {
+    pub slots: Vec<slots::SaveSlot>,
+    pub name: String,
+    pub scroll: usize,
+    /// Why the last save or load didn't work.
+    pub error: Option<String>,
+}
+impl SlotPanel {
+    pub fn new() -> SlotPanel {
+        SlotPanel {slots: slots::list_slots(), name: String::new(), scroll: 0, error: None}
+    }
+    pub fn refresh(&mut self) {
+        self.slots = slots::list_slots();
+        self.scroll = self.scroll.min(self.slots.len().saturating_sub(PANEL_VISIBLE_ROWS));
+    }
+    fn row_rect(row: usize) -> graphics::Rect {
+        graphics::Rect::new(PANEL.x+BORDER_SIZE, PANEL_LIST_Y+row as f32*PANEL_ROW_HEIGHT, PANEL.w-2.0*BORDER_SIZE, PANEL_ROW_HEIGHT-4.0)
+    }
+    fn load_button(row: usize) -> graphics::Rect {
+        let row = SlotPanel::row_rect(row);
+        graphics::Rect::new(row.x+row.w-120.0, row.y+2.0, 55.0, row.h-4.0)
+    }
+    fn delete_button(row: usize) -> graphics::Rect {
+        let row = SlotPanel::row_rect(row);
+        graphics::Rect::new(row.x+row.w-60.0, row.y+2.0, 55.0, row.h-4.0)
+    }
+}
+
+// Layout of the new game dialog, a label column followed by the fields.
+pub const DIALOG_LABEL_WIDTH: f32 = 120.0;
+pub const DIALOG_FIELD_WIDTH: f32 = 160.0;
+
+/// The dialog to start a new game with other players, bot strength, time control or position.
+pub struct NewGamePanel {
+    pub player_white: Player,
+    pub player_black: Player,
+    pub strength: usize,
+    pub time_control: usize,
+    pub fen: String,
+    pub error: Option<String>,
+}
+impl NewGamePanel {
+    pub fn new<P: Position>(game: &Game<P>) -> NewGamePanel {
+        let strength = BOT_STRENGTHS.iter().position(|&(_, ms)| ms == game.bot_think_ms).unwrap_or(DEFAULT_BOT_STRENGTH);
+        let time_control = TIME_CONTROLS.iter()
+            .position(|tc| tc.and_then(clock::TimeControl::parse) == game.clock.as_ref().map(|c| c.time_control))
+            .unwrap_or(0);
+        NewGamePanel {
+            player_white: game.player_white,
+            player_black: game.player_black,
+            strength,
+            time_control,
+            fen: String::new(),
+            error: None,
+        }
+    }
+    fn row(row: usize) -> f32 {
+        PANEL.y + BORDER_SIZE + (row+1) as f32*PANEL_ROW_HEIGHT
+    }
+    fn field(row: usize) -> graphics::Rect {
+        graphics::Rect::new(PANEL.x+BORDER_SIZE+DIALOG_LABEL_WIDTH, NewGamePanel::row(row), DIALOG_FIELD_WIDTH, 22.0)
+    }
+    fn fen_field() -> graphics::Rect {
+        graphics::Rect::new(PANEL.x+BORDER_SIZE, NewGamePanel::row(5), PANEL.w-2.0*BORDER_SIZE, 22.0)
+    }
+    fn start_button() -> graphics::Rect {
+        graphics::Rect::new(PANEL.x+PANEL.w-2.0*BORDER_SIZE-120.0, NewGamePanel::row(8), 60.0, 22.0)
+    }
+    fn cancel_button() -> graphics::Rect {
+        graphics::Rect::new(PANEL.x+PANEL.w-BORDER_SIZE-60.0, NewGamePanel::row(8), 60.0, 22.0)
+    }
+    fn player_label(player: Player) -> &'static str {
+        if player == Player::HUMAN {"Human"} else {"Bot"}
+    }
+    fn toggle(player: Player) -> Player {
+        if player == Player::HUMAN {Player::BOT} else {Player::HUMAN}
+    }
 }
 
-impl event::EventHandler<ggez::GameError> for Game {
+impl<P: Position> event::EventHandler<ggez::GameError> for Game<P> {
     fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        if *self.get_current_player_type() == Player::BOT {
-            cpu::make_bot_move(self, false);
+        self.check_flag();
+        self.check_no_moves();
+        if self.game_over.is_some() {
+            return Ok(());
+        }
+        if *self.get_current_player_type() == Player::BOT && !self.overlay_open() && self.view_ply.is_none() {
+            cpu::make_bot_move(self);
         }
 
         Ok(())
@@ -57,41 +268,60 @@ impl event::EventHandler<ggez::GameError> for Game {
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
         let mut canvas =
-            graphics::Canvas::from_frame(ctx, graphics::Color::from([0.1, 0.2, 0.3, 1.0]));
+            graphics::Canvas::from_frame(ctx, self.theme.background);
 
-        for x in 0..8 {
-            for y in 0..8 {
-                let square = if (x+y)%2==0 {&self.square_light} else {&self.square_dark};
-                canvas.draw(square, Vec2::new((x as f32)*SQUARE_SIZE+DX, (y as f32)*SQUARE_SIZE+DY));
+        for pos in 0..64 {
+            let (x, y) = util::pos_to_xy(pos);
+            let square = if (x+y)%2==0 {&self.square_light} else {&self.square_dark};
+            canvas.draw(square, self.square_to_screen(pos));
+        }
+        self.draw_coordinates(&mut canvas);
+        let board = self.displayed_board();
+        if let Some(&(from, to)) = self.displayed_ply().checked_sub(1).and_then(|idx| self.history.get(idx)) {
+            canvas.draw(&self.square_last_move, self.square_to_screen(from));
+            canvas.draw(&self.square_last_move, self.square_to_screen(to));
+        }
+        if self.show_attacked {
+            for pos in util::BitIter::new(board.attacked_squares(!board.is_whites_turn())) {
+                canvas.draw(&self.square_attacked, self.square_to_screen(pos));
             }
         }
-        for x in 0..8 {
-            for y in 0..8 {
-                let piece = self.board.board_pos[x+y*8];
-                if piece == 0 {continue;}
-                let sprite = &self.pieces[piece-1];
-                let scale = mint::Vector2 {x: SQUARE_SIZE / (sprite.width() as f32), y: SQUARE_SIZE / (sprite.height() as f32)};
-                let dest = mint::Vector2 {x: (x as f32)*SQUARE_SIZE+DX, y: (y as f32)*SQUARE_SIZE+DY};
-                let draw_param = graphics::DrawParam::new()
-                    .dest(dest)
-                    .scale(scale);
-                canvas.draw(sprite, draw_param);
+        if board.is_in_check(board.is_whites_turn()) {
+            if let Some(king_pos) = board.find_king(board.is_whites_turn()) {
+                canvas.draw(&self.square_check, self.square_to_screen(king_pos));
+            }
+            for pos in util::BitIter::new(board.checkers()) {
+                canvas.draw(&self.square_check, self.square_to_screen(pos));
             }
         }
-        if self.selected_square.is_some() {
-            let x = self.selected_square.unwrap() % 8;
-            let y = self.selected_square.unwrap() / 8;
-            canvas.draw(&self.square_highlight, Vec2::new((x as f32)*SQUARE_SIZE+DX, (y as f32)*SQUARE_SIZE+DY));
+        for pos in 0..64 {
+            let piece = board.piece_at(pos);
+            if piece == 0 || self.dragging == Some(pos) {continue;}
+            let sprite = &self.pieces[piece-1];
+            let scale = mint::Vector2 {x: SQUARE_SIZE / (sprite.width() as f32), y: SQUARE_SIZE / (sprite.height() as f32)};
+            let draw_param = graphics::DrawParam::new()
+                .dest(self.square_to_screen(pos))
+                .scale(scale);
+            canvas.draw(sprite, draw_param);
+        }
+        if let Some(pos) = self.selected_square {
+            canvas.draw(&self.square_highlight, self.square_to_screen(pos));
 
-            let pos = self.selected_square.unwrap();
-            let mut moveable = self.board.get_moveable_squares_with_checks(pos);
+            let moveable = legal_targets(board, pos);
             for p in util::BitIter::new(moveable) {
-                let (x, y) = util::pos_to_xy(p);
-                canvas.draw(&self.square_moveable, Vec2::new((x as f32)*SQUARE_SIZE+DX, (y as f32)*SQUARE_SIZE+DY));
+                let target = if board.piece_at(p) != 0 {&self.square_capturable} else {&self.square_moveable};
+                canvas.draw(target, self.square_to_screen(p));
             }
         }
+        if let Some(pos) = self.dragging {
+            let sprite = &self.pieces[board.piece_at(pos)-1];
+            let scale = mint::Vector2 {x: SQUARE_SIZE / (sprite.width() as f32), y: SQUARE_SIZE / (sprite.height() as f32)};
+            let mouse = ctx.mouse.position();
+            let dest = mint::Vector2 {x: mouse.x-SQUARE_SIZE/2.0, y: mouse.y-SQUARE_SIZE/2.0};
+            canvas.draw(sprite, graphics::DrawParam::new().dest(dest).scale(scale));
+        }
         
-        let board_rating = util::sigmoid(self.board.rate_board() as f32 / -300.0);
+        let board_rating = util::sigmoid(board.evaluate() as f32 / -300.0);
         let white = graphics::Mesh::new_rectangle(
             ctx,
             graphics::DrawMode::fill(),
@@ -104,73 +334,84 @@ impl event::EventHandler<ggez::GameError> for Game {
             graphics::Rect::new(0.0,0.0, 20.0, 8.0*SQUARE_SIZE*board_rating),
             Color::BLACK,
         )?;
+        let black_y = if self.flipped {BORDER_SIZE + 8.0*SQUARE_SIZE*(1.0-board_rating)} else {BORDER_SIZE};
         canvas.draw(&white, Vec2::new(DX-BORDER_SIZE-20.0, BORDER_SIZE));
-        canvas.draw(&black, Vec2::new(DX-BORDER_SIZE-20.0, BORDER_SIZE));
-        let button = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(0.0,0.0, 40.0, 20.0),
-            Color::from_rgb(00, 00, 00),
-        )?;
-        canvas.draw(&button, Vec2::new(BORDER_SIZE, BORDER_SIZE));
-        let mut text = graphics::Text::new("Save");
-        text.set_bounds(Vec2::new(400.0, f32::INFINITY))
-            .set_layout(graphics::TextLayout {
-                h_align: graphics::TextAlign::Middle,
-                v_align: graphics::TextAlign::Middle,
-            });
-        canvas.draw(&text, Vec2::new(BORDER_SIZE+20.0, BORDER_SIZE+10.0));
-        let button = graphics::Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            graphics::Rect::new(0.0,0.0, 40.0, 20.0),
-            Color::from_rgb(00, 00, 00),
-        )?;
-        canvas.draw(&button, Vec2::new(BORDER_SIZE, 2.0*BORDER_SIZE+20.0));
-        let mut text = graphics::Text::new("Load");
-        text.set_bounds(Vec2::new(400.0, f32::INFINITY))
-            .set_layout(graphics::TextLayout {
-                h_align: graphics::TextAlign::Middle,
-                v_align: graphics::TextAlign::Middle,
-            });
-        canvas.draw(&text, Vec2::new(BORDER_SIZE+20.0, 2.0*BORDER_SIZE+30.0));
+        canvas.draw(&black, Vec2::new(DX-BORDER_SIZE-20.0, black_y));
+        draw_button(ctx, &mut canvas, &self.theme, SAVE_BUTTON, "Save")?;
+        draw_button(ctx, &mut canvas, &self.theme, LOAD_BUTTON, "Load")?;
+        draw_button(ctx, &mut canvas, &self.theme, UNDO_BUTTON, "Undo")?;
+        draw_button(ctx, &mut canvas, &self.theme, REDO_BUTTON, "Redo")?;
+        draw_button(ctx, &mut canvas, &self.theme, BRANCH_BUTTON, if self.branch_mode == BranchMode::BRANCH {"Branch"} else {"Block"})?;
+        draw_button(ctx, &mut canvas, &self.theme, FLIP_BUTTON, "Flip")?;
+        draw_button(ctx, &mut canvas, &self.theme, NEW_GAME_BUTTON, "New game")?;
+        draw_button(ctx, &mut canvas, &self.theme, ATTACKED_BUTTON, if self.show_attacked {"Hide att."} else {"Attacked"})?;
+        self.draw_clocks(&mut canvas);
+        self.draw_material(&mut canvas);
+        self.draw_move_list(ctx, &mut canvas)?;
+        if self.slot_panel.is_some() {
+            self.draw_slot_panel(ctx, &mut canvas)?;
+        }
+        if self.new_game_panel.is_some() {
+            self.draw_new_game_panel(ctx, &mut canvas)?;
+        }
         canvas.finish(ctx)?;
 
         Ok(())
     }
 
     fn mouse_button_down_event(
-        self: &mut Game,
+        &mut self,
         _ctx: &mut Context,
         button: MouseButton,
         x: f32,
         y: f32,
     ) -> Result<(), GameError> {
+        if button == MouseButton::Left && self.slot_panel.is_some() {
+            self.click_slot_panel(x, y);
+            return Ok(());
+        }
+        if button == MouseButton::Left && self.new_game_panel.is_some() {
+            self.click_new_game_panel(x, y);
+            return Ok(());
+        }
         if button == MouseButton::Left {
             if x >= DX {
-                // Store the clicked position
-                let clicked_pos = (((x-DX) / SQUARE_SIZE).floor() + 8.0*((y-DY)/SQUARE_SIZE).floor()) as usize;
-                if *self.get_current_player_type() == Player::HUMAN {
-                    if self.selected_square.is_some() {
-                        if (self.board.board_pos[self.selected_square.unwrap()] < 7) == self.board.is_whites_turn {
-                            let mut moveable = self.board.get_moveable_squares(self.selected_square.unwrap());
-                            for p in util::BitIter::new(moveable) {
-                                if p == clicked_pos {
-                                    self.move_square(self.selected_square.unwrap(), clicked_pos);
-                                }
-                            }
-                        }
+                if let Some(clicked_pos) = self.square_at(x, y) {
+                    let selected = self.selected_square;
+                    if selected.is_some() && self.try_human_move(selected.unwrap(), clicked_pos) {
+                        return Ok(());
                     }
-                }
-                if clicked_pos < 64 { //unsigned, so can't be bellow 0.
                     self.selected_square = Some(clicked_pos);
+                    if self.can_human_move_piece(clicked_pos) {
+                        self.dragging = Some(clicked_pos); //picked up, dropped in mouse_button_up_event.
+                    }
                 }
             } else {
-                if x > BORDER_SIZE && y > BORDER_SIZE && x < BORDER_SIZE+40.0 && y < BORDER_SIZE+20.0 {
-                    self.save_game("games/lagame.txt");
+                if SAVE_BUTTON.contains([x, y]) || LOAD_BUTTON.contains([x, y]) {
+                    self.slot_panel = Some(SlotPanel::new());
+                }
+                if UNDO_BUTTON.contains([x, y]) {
+                    self.undo();
+                }
+                if REDO_BUTTON.contains([x, y]) {
+                    self.redo();
+                }
+                if BRANCH_BUTTON.contains([x, y]) {
+                    self.branch_mode = if self.branch_mode == BranchMode::BRANCH {BranchMode::BLOCK} else {BranchMode::BRANCH};
+                }
+                if NEW_GAME_BUTTON.contains([x, y]) {
+                    self.new_game_panel = Some(NewGamePanel::new(self));
+                }
+                if ATTACKED_BUTTON.contains([x, y]) {
+                    self.show_attacked = !self.show_attacked;
+                }
+                if FLIP_BUTTON.contains([x, y]) {
+                    self.flipped = !self.flipped;
                 }
-                if x > BORDER_SIZE && y > 20.0+2.0*BORDER_SIZE && x < BORDER_SIZE+40.0 && y < 2.0*BORDER_SIZE+40.0 {
-                    self.load_game("games/lagame.txt")
+                if MOVE_LIST.contains([x, y]) {
+                    if let Some(ply) = self.move_list_ply_at(x, y) {
+                        self.set_view_ply(ply);
+                    }
                 }
             }
         }
@@ -178,16 +419,170 @@ impl event::EventHandler<ggez::GameError> for Game {
     }
     fn mouse_button_up_event(
             &mut self,
-            ctx: &mut Context,
+            _ctx: &mut Context,
             button: MouseButton,
             x: f32,
             y: f32,
         ) -> Result<(), ggez::GameError> {
-        self.mouse_button_down_event(ctx, button, x, y)
+        if button != MouseButton::Left {
+            return Ok(());
+        }
+        if let Some(from) = self.dragging.take() {
+            match self.square_at(x, y) {
+                Some(to) if to != from => {
+                    self.try_human_move(from, to); //an illegal drop puts the piece back.
+                }
+                _ => {} //dropped where it was picked up, the second click can still move it.
+            }
+        }
+        Ok(())
+    }
+
+    fn mouse_wheel_event(&mut self, ctx: &mut Context, _x: f32, y: f32) -> GameResult {
+        if !self.overlay_open() && MOVE_LIST.contains(ctx.mouse.position()) {
+            if y != 0.0 {
+                self.scroll_move_list(if y > 0.0 {-1} else {1});
+            }
+        }
+        if let Some(panel) = &mut self.slot_panel {
+            if y > 0.0 {
+                panel.scroll = panel.scroll.saturating_sub(1);
+            } else if y < 0.0 && panel.scroll + PANEL_VISIBLE_ROWS < panel.slots.len() {
+                panel.scroll += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, _repeated: bool) -> GameResult {
+        match input.keycode {
+            Some(KeyCode::Escape) => {
+                if self.overlay_open() {
+                    self.slot_panel = None;
+                    self.new_game_panel = None;
+                } else {
+                    ctx.request_quit();
+                }
+            }
+            Some(KeyCode::Z) if input.mods.contains(KeyMods::CTRL) && !self.overlay_open() => {
+                if input.mods.contains(KeyMods::SHIFT) {
+                    self.redo();
+                } else {
+                    self.undo();
+                }
+            }
+            Some(KeyCode::Y) if input.mods.contains(KeyMods::CTRL) && !self.overlay_open() => {
+                self.redo();
+            }
+            Some(KeyCode::Left) if !self.overlay_open() => {
+                let ply = self.displayed_ply();
+                self.set_view_ply(ply.saturating_sub(1));
+            }
+            Some(KeyCode::Right) if !self.overlay_open() => {
+                let ply = self.displayed_ply();
+                self.set_view_ply(ply+1);
+            }
+            Some(KeyCode::Home) | Some(KeyCode::Up) if !self.overlay_open() => {
+                self.set_view_ply(0);
+            }
+            Some(KeyCode::End) | Some(KeyCode::Down) if !self.overlay_open() => {
+                self.set_view_ply(self.history.len());
+            }
+            Some(KeyCode::A) if !self.overlay_open() => {
+                self.show_attacked = !self.show_attacked;
+            }
+            Some(KeyCode::F) if !self.overlay_open() => {
+                self.flipped = !self.flipped;
+            }
+            Some(KeyCode::V) if input.mods.contains(KeyMods::CTRL) => {
+                if let Some(panel) = &mut self.new_game_panel {
+                    match read_clipboard() {
+                        Some(text) => panel.fen = text.trim().to_string(),
+                        None => panel.error = Some("Couldn't read the clipboard.".to_string()),
+                    }
+                }
+            }
+            Some(KeyCode::Back) => {
+                if let Some(panel) = &mut self.slot_panel {
+                    panel.name.pop();
+                }
+                if let Some(panel) = &mut self.new_game_panel {
+                    panel.fen.pop();
+                }
+            }
+            Some(KeyCode::Return) | Some(KeyCode::NumpadEnter) => {
+                if self.slot_panel.is_some() {
+                    self.save_to_slot();
+                }
+                if self.new_game_panel.is_some() {
+                    self.start_new_game();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, character: char) -> GameResult {
+        if let Some(panel) = &mut self.slot_panel {
+            if !character.is_control() && panel.name.len() < 32 {
+                panel.name.push(character);
+            }
+        }
+        if let Some(panel) = &mut self.new_game_panel {
+            if !character.is_control() && panel.fen.len() < 100 {
+                panel.fen.push(character);
+            }
+        }
+        Ok(())
     }
 }
 
-pub fn main() -> GameResult<()> {
+/// Text on the system clipboard. ggez can't read it, so this asks the usual command line tools.
+pub fn read_clipboard() -> Option<String> {
+    let commands: [(&str, &[&str]); 5] = [
+        ("wl-paste", &["--no-newline"]),
+        ("xclip", &["-selection", "clipboard", "-o"]),
+        ("xsel", &["--clipboard", "--output"]),
+        ("pbpaste", &[]),
+        ("powershell", &["-command", "Get-Clipboard"]),
+    ];
+    for (command, args) in commands {
+        if let Ok(output) = std::process::Command::new(command).args(args).output() {
+            if output.status.success() {
+                return String::from_utf8(output.stdout).ok();
+            }
+        }
+    }
+    None
+}
+
+/// Squares the piece on `pos` can go to without leaving its king in check.
+pub fn legal_targets<P: Position>(board: &P, pos: usize) -> u64 {
+    util::BitIter::new(board.moveable_squares(pos))
+        .filter(|&target| board.is_legal_move((pos, target)))
+        .fold(0u64, |moveable, target| moveable | (1<<target))
+}
+
+pub fn draw_button(ctx: &mut Context, canvas: &mut graphics::Canvas, theme: &Theme, rect: graphics::Rect, label: &str) -> GameResult {
+    let button = graphics::Mesh::new_rectangle(
+        ctx,
+        graphics::DrawMode::fill(),
+        graphics::Rect::new(0.0,0.0, rect.w, rect.h),
+        theme.button,
+    )?;
+    canvas.draw(&button, Vec2::new(rect.x, rect.y));
+    let mut text = graphics::Text::new(label);
+    text.set_bounds(Vec2::new(400.0, f32::INFINITY))
+        .set_layout(graphics::TextLayout {
+            h_align: graphics::TextAlign::Middle,
+            v_align: graphics::TextAlign::Middle,
+        });
+    canvas.draw(&text, Vec2::new(rect.x+rect.w/2.0, rect.y+rect.h/2.0));
+    Ok(())
+}
+
+pub fn main(options: &crate::cli::Options) -> GameResult<()> {
     let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
         let mut path = path::PathBuf::from(manifest_dir);
         path.push("resources");
@@ -202,96 +597,590 @@ pub fn main() -> GameResult<()> {
 
     let (mut ctx, mut event_loop) = cb.build()?;
 
-    let args = env::args().collect::<Vec<String>>();
-    let mut player_white = Player::HUMAN;
-    let mut player_black = Player::BOT;
-    if args.len() >= 2 && args[1] == "b" {
-        player_white = Player::BOT;
-    }
-    if args.len() >= 3 && args[2] == "h" {
-        player_black = Player::HUMAN;
+    let mut state = Game::<Board>::new(&mut ctx, options.player_white, options.player_black)?;
+    match options.load_game() {
+        Ok(saved) => state.load_saved_game(saved),
+        Err(e) => return Err(GameError::CustomError(e)),
     }
-
-    let mut state = Game::new(&mut ctx, player_white, player_black)?;
-    if args.len() >= 2 && args[1] == "test" {
-        test::benchmark_performance(&mut state);
+    if options.time_control.is_some() {
+        state.set_time_control(options.time_control);
     }
-    if args.len() >= 2 && args[1] == "test_rating" {
-        test::benchmark_quality(&mut state);
+    if let Some(movetime) = options.movetime_ms {
+        state.bot_think_ms = movetime;
     }
+    println!("Seed: {}", options.seed);
+    state.search_context = cpu::SearchContext::new(options.engine.clone(), options.seed);
     event::run(ctx, event_loop, state) // Dereference event_loop
 }
 
-impl Game {
-    pub fn save_game(&mut self, file_path: &str) {
+impl<P: Position> Game<P> {
+    pub fn save_game(&mut self, file_path: &str) -> Result<(), String> {
         println!("Saving game!");
-        let mut file = match File::create(file_path) {
-            Ok(f) => f,
-            Err(e) => panic!("Failed to create file: {}", e),
-        };
-
-        let mut data = "".to_string();
-        for (from, to) in self.history.iter() {
-            data += format!("{} {}\n", from, to).as_str();
+        if let Some(dir) = path::Path::new(file_path).parent() {
+            let _ = std::fs::create_dir_all(dir);
         }
-        let _ = file.write_all(data.as_bytes());
+        let mut file = File::create(file_path).map_err(|e| format!("Failed to create file: {}", e))?;
+
+        let saved = slots::SavedGame {
+            start_board: self.start_board.clone(),
+            history: self.history.clone(),
+            clock: self.clock.clone(),
+        };
+        file.write_all(saved.to_file_string().as_bytes()).map_err(|e| format!("Failed to write file: {}", e))?;
         let _ = file.flush();
+        Ok(())
     }
-    pub fn load_game(&mut self, file_path: &str) {
+    /// Replaces the current game with the one in `file_path`, or leaves it be when the file can't be read.
+    pub fn load_game(&mut self, file_path: &str) -> Result<(), String> {
         println!("Loading game!");
-        let mut file = match File::open(file_path) {
-            Ok(f) => f,
-            Err(e) => panic!("Failed to open file: {}", e),
+        let saved = slots::SavedGame::read(file_path)?;
+        self.load_saved_game(saved);
+        Ok(())
+    }
+    pub fn load_saved_game(&mut self, saved: slots::SavedGame<P>) {
+        self.start_board = saved.start_board;
+        self.replay(saved.history);
+        self.redo.clear();
+        self.clock = saved.clock;
+        self.reset_clocks();
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.board.is_whites_turn());
+        }
+    }
+    pub fn save_to_slot(&mut self) {
+        let name = match &self.slot_panel {
+            Some(panel) if !slots::sanitize_name(&panel.name).is_empty() => panel.name.clone(),
+            _ => slots::default_name(),
+        };
+        let saved = self.save_game(&slots::slot_path(&name));
+        if let Some(panel) = &mut self.slot_panel {
+            match saved {
+                Ok(()) => panel.name.clear(),
+                Err(e) => panel.error = Some(e),
+            }
+            panel.refresh();
+        }
+    }
+    fn click_slot_panel(&mut self, x: f32, y: f32) {
+        let panel = match &mut self.slot_panel {
+            Some(panel) => panel,
+            None => return,
         };
+        panel.error = None;
+        if PANEL_CLOSE_BUTTON.contains([x, y]) || !PANEL.contains([x, y]) {
+            self.slot_panel = None;
+            return;
+        }
+        if PANEL_SAVE_BUTTON.contains([x, y]) {
+            self.save_to_slot();
+            return;
+        }
+        let visible = panel.slots.len().saturating_sub(panel.scroll).min(PANEL_VISIBLE_ROWS);
+        for row in 0..visible {
+            let idx = panel.scroll + row;
+            if SlotPanel::load_button(row).contains([x, y]) {
+                let path = panel.slots[idx].path.to_string_lossy().to_string();
+                match self.load_game(&path) {
+                    Ok(()) => self.slot_panel = None,
+                    Err(e) => if let Some(panel) = &mut self.slot_panel {
+                        panel.error = Some(e);
+                    },
+                }
+                return;
+            }
+            if SlotPanel::delete_button(row).contains([x, y]) {
+                slots::delete_slot(&panel.slots[idx]);
+                panel.refresh();
+                return;
+            }
+            if SlotPanel::row_rect(row).contains([x, y]) {
+                panel.name = panel.slots[idx].name.clone(); //overwrite this slot on the next save.
+                return;
+            }
+        }
+    }
+    fn draw_slot_panel(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let panel = match &self.slot_panel {
+            Some(panel) => panel,
+            None => return Ok(()),
+        };
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, PANEL.w, PANEL.h),
+            self.theme.panel,
+        )?;
+        canvas.draw(&background, Vec2::new(PANEL.x, PANEL.y));
+        canvas.draw(&graphics::Text::new("Saved games"), Vec2::new(PANEL.x+BORDER_SIZE, PANEL.y+BORDER_SIZE));
 
-        let mut contents = String::new();
-        match file.read_to_string(&mut contents) {
-            Ok(_) => {
-                let mut history: Vec<(usize, usize)> = vec![];
-                for line in contents.split("\n").into_iter() {
-                    if line.is_empty() {continue;}
-                    let move_: Vec<&str> = line.split(" ").collect();
-                    let from: usize;
-                    let to: usize;
-                    match move_[0].to_string().parse::<usize>() {
-                        Ok(parsed_move) => {
-                            from = parsed_move;
-                        }
-                        Err(e) => {
-                            panic!("Corrupted file!: {}", e);
-                        },
-                    }
-                    match move_[1].to_string().parse::<usize>() {
-                        Ok(parsed_move) => {
-                            to = parsed_move;
-                        }
-                        Err(e) => {
-                            panic!("Corrupted file!: {}", e);
-                        },
-                    }
-                    history.push((from, to));
+        canvas.draw(&graphics::Text::new("Name:"), Vec2::new(PANEL.x+BORDER_SIZE, PANEL_NAME_FIELD.y+3.0));
+        let field = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            graphics::Rect::new(0.0, 0.0, PANEL_NAME_FIELD.w, PANEL_NAME_FIELD.h),
+            Color::WHITE,
+        )?;
+        canvas.draw(&field, Vec2::new(PANEL_NAME_FIELD.x, PANEL_NAME_FIELD.y));
+        canvas.draw(&graphics::Text::new(format!("{}_", panel.name)), Vec2::new(PANEL_NAME_FIELD.x+4.0, PANEL_NAME_FIELD.y+3.0));
+        draw_button(ctx, canvas, &self.theme, PANEL_SAVE_BUTTON, "Save")?;
+        draw_button(ctx, canvas, &self.theme, PANEL_CLOSE_BUTTON, "Close")?;
+
+        if panel.slots.is_empty() {
+            canvas.draw(&graphics::Text::new("No saved games yet."), Vec2::new(PANEL.x+BORDER_SIZE, PANEL_LIST_Y));
+        }
+        for (row, slot) in panel.slots.iter().skip(panel.scroll).take(PANEL_VISIBLE_ROWS).enumerate() {
+            let rect = SlotPanel::row_rect(row);
+            let label = format!("{:<20} {}  {:>3} moves", slot.name, slot.date(), slot.move_count);
+            canvas.draw(&graphics::Text::new(label), Vec2::new(rect.x, rect.y+5.0));
+            draw_button(ctx, canvas, &self.theme, SlotPanel::load_button(row), "Load")?;
+            draw_button(ctx, canvas, &self.theme, SlotPanel::delete_button(row), "Delete")?;
+        }
+        if let Some(error) = &panel.error {
+            canvas.draw(&graphics::Text::new(error.as_str()), graphics::DrawParam::new().dest(Vec2::new(PANEL.x+BORDER_SIZE, PANEL.y+PANEL.h-BORDER_SIZE-20.0)).color(self.theme.check));
+        }
+        Ok(())
+    }
+    /// Rebuilds the board from the starting position, so castling, en passant and
+    /// the draw tracking end up exactly as they were when the moves were first played.
+    pub fn replay(&mut self, history: Vec<(usize, usize)>) {
+        self.board = self.start_board.clone();
+        self.history = vec![];
+        self.move_names = vec![];
+        self.captures = vec![];
+        self.selected_square = None;
+        self.dragging = None;
+        self.view_ply = None;
+        self.view_board = None;
+        self.game_over = None; //`update` ends it again if it still is over.
+        for (from, to) in history {
+            self.push_move(from, to);
+        }
+    }
+    /// The ply shown on screen, `history.len()` when looking at the current position.
+    pub fn displayed_ply(&self) -> usize {
+        self.view_ply.unwrap_or(self.history.len())
+    }
+    pub fn displayed_board(&self) -> &P {
+        self.view_board.as_ref().unwrap_or(&self.board)
+    }
+    pub fn board_at(&self, ply: usize) -> P {
+        let mut board = self.start_board.clone();
+        for &(from, to) in self.history.iter().take(ply) {
+            board.make_move((from, to));
+        }
+        board
+    }
+    /// Shows the position after `ply` moves without touching the game itself.
+    pub fn set_view_ply(&mut self, ply: usize) {
+        let ply = ply.min(self.history.len());
+        if ply == self.displayed_ply() {
+            return;
+        }
+        self.selected_square = None;
+        self.move_list_scroll = None;
+        if ply == self.history.len() {
+            self.view_ply = None;
+            self.view_board = None;
+        } else {
+            self.view_ply = Some(ply);
+            self.view_board = Some(self.board_at(ply));
+        }
+    }
+    /// Top left corner of a square on screen, black's side is at the bottom when flipped.
+    pub fn square_to_screen(&self, pos: usize) -> Vec2 {
+        let (mut x, mut y) = util::pos_to_xy(pos);
+        if self.flipped {
+            (x, y) = (7-x, 7-y);
+        }
+        Vec2::new((x as f32)*SQUARE_SIZE+DX, (y as f32)*SQUARE_SIZE+DY)
+    }
+    /// The board square under a point in the window.
+    pub fn square_at(&self, x: f32, y: f32) -> Option<usize> {
+        let file = ((x-DX) / SQUARE_SIZE).floor();
+        let rank = ((y-DY) / SQUARE_SIZE).floor();
+        if file < 0.0 || rank < 0.0 || file > 7.0 || rank > 7.0 {
+            return None;
+        }
+        let (file, rank) = (file as usize, rank as usize);
+        if self.flipped {
+            Some((7-file) + 8*(7-rank))
+        } else {
+            Some(file + 8*rank)
+        }
+    }
+    /// Rank numbers along the left edge and file letters along the bottom edge of the board.
+    fn draw_coordinates(&self, canvas: &mut graphics::Canvas) {
+        let bottom_row = if self.flipped {0} else {7};
+        let left_column = if self.flipped {7} else {0};
+        for i in 0..8 {
+            for (pos, label, corner) in [
+                (left_column + 8*i, (8-i).to_string(), Vec2::new(2.0, 1.0)),
+                (i + 8*bottom_row, ((b'a' + i as u8) as char).to_string(), Vec2::new(SQUARE_SIZE-9.0, SQUARE_SIZE-15.0)),
+            ] {
+                let (x, y) = util::pos_to_xy(pos);
+                let colour = if (x+y)%2==0 {self.theme.dark_square} else {self.theme.light_square};
+                let mut text = graphics::Text::new(label);
+                text.set_scale(12.0);
+                canvas.draw(&text, graphics::DrawParam::new().dest(self.square_to_screen(pos) + corner).color(colour));
+            }
+        }
+    }
+    /// Whether a human may pick up the piece on `pos` right now.
+    fn can_human_move_piece(&self, pos: usize) -> bool {
+        let piece = self.displayed_board().piece_at(pos);
+        *self.get_current_player_type() == Player::HUMAN
+            && self.game_over.is_none()
+            && self.can_move_from_view()
+            && piece != 0
+            && util::is_piece_white(piece) == self.displayed_board().is_whites_turn()
+    }
+    /// Plays the move for the human if it is legal, returns whether it was played.
+    pub fn try_human_move(&mut self, from: usize, to: usize) -> bool {
+        if !self.can_human_move_piece(from) || legal_targets(self.displayed_board(), from) & (1<<to) == 0 {
+            return false;
+        }
+        self.branch_from_view();
+        self.move_square(from, to);
+        self.selected_square = None;
+        true
+    }
+    fn can_move_from_view(&self) -> bool {
+        self.view_ply.is_none() || self.branch_mode == BranchMode::BRANCH
+    }
+    /// Drops the moves after the viewed position, so a new move starts a new line from there.
+    fn branch_from_view(&mut self) {
+        if let Some(ply) = self.view_ply {
+            let history = self.history[..ply].to_vec();
+            let selected = self.selected_square;
+            self.replay(history);
+            self.restore_clock();
+            self.selected_square = selected;
+        }
+    }
+    fn move_list_first_row(&self) -> usize {
+        if let Some(scroll) = self.move_list_scroll {
+            return scroll;
+        }
+        let current_row = self.displayed_ply().saturating_sub(1) / 2;
+        (current_row+1).saturating_sub(MOVE_LIST_ROWS)
+    }
+    fn move_list_ply_at(&self, x: f32, y: f32) -> Option<usize> {
+        let row = ((y-MOVE_LIST.y) / MOVE_ROW_HEIGHT) as usize + self.move_list_first_row();
+        let column = x - MOVE_LIST.x - MOVE_NUMBER_WIDTH;
+        if column < 0.0 {
+            return None;
+        }
+        let idx = row*2 + (column >= MOVE_WIDTH) as usize;
+        if idx < self.history.len() {
+            Some(idx+1)
+        } else {
+            None
+        }
+    }
+    fn draw_move_list(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let first_row = self.move_list_first_row();
+        let current = self.displayed_ply().checked_sub(1);
+        for row in 0..MOVE_LIST_ROWS {
+            let move_number = first_row + row;
+            if move_number*2 >= self.history.len() {
+                break;
+            }
+            let y = MOVE_LIST.y + row as f32*MOVE_ROW_HEIGHT;
+            canvas.draw(&graphics::Text::new(format!("{}.", move_number+1)), Vec2::new(MOVE_LIST.x, y+2.0));
+            for side in 0..2 {
+                let idx = move_number*2 + side;
+                if idx >= self.history.len() {
+                    break;
                 }
-                self.board = Board::new();
-                self.history = vec![];
-                for (from, to) in history {
-                    self.move_square(from, to);
+                let x = MOVE_LIST.x + MOVE_NUMBER_WIDTH + side as f32*MOVE_WIDTH;
+                if current == Some(idx) {
+                    let highlight = graphics::Mesh::new_rectangle(
+                        ctx,
+                        graphics::DrawMode::fill(),
+                        graphics::Rect::new(0.0, 0.0, MOVE_WIDTH-2.0, MOVE_ROW_HEIGHT-2.0),
+                        self.theme.move_list_highlight,
+                    )?;
+                    canvas.draw(&highlight, Vec2::new(x-2.0, y));
                 }
-            },
-            Err(e) => panic!("Failed to read the file: {}", e),
+                canvas.draw(&graphics::Text::new(self.move_names[idx].as_str()), Vec2::new(x, y+2.0));
+            }
         }
+        Ok(())
     }
-    pub fn move_square(&mut self, old: usize, new: usize) {
+    fn scroll_move_list(&mut self, rows: isize) {
+        let total_rows = (self.history.len()+1) / 2;
+        let max_scroll = total_rows.saturating_sub(MOVE_LIST_ROWS);
+        let first_row = self.move_list_first_row() as isize + rows;
+        self.move_list_scroll = Some(first_row.clamp(0, max_scroll as isize) as usize);
+    }
+    /// Material of both sides on the displayed board, kings left out.
+    fn material(&self, white: bool) -> i32 {
+        let board = self.displayed_board();
+        (0..64)
+            .map(|pos| board.piece_at(pos))
+            .filter(|&piece| piece != 0 && util::is_piece_white(piece) == white && util::PIECE_TO_COLOURLESS[piece] != KING)
+            .map(|piece| util::PIECE_VALUES[piece].abs())
+            .sum()
+    }
+    /// The pieces `white` has taken up to the displayed move, most valuable first.
+    fn captured_by(&self, white: bool) -> Vec<usize> {
+        let mut captured: Vec<usize> = self.captures.iter()
+            .take(self.displayed_ply())
+            .cloned()
+            .filter(|&piece| piece != 0 && util::is_piece_white(piece) != white)
+            .collect();
+        captured.sort_by_key(|&piece| -util::PIECE_VALUES[piece].abs());
+        captured
+    }
+    fn draw_material(&self, canvas: &mut graphics::Canvas) {
+        let difference = self.material(true) - self.material(false);
+        for (row, white) in [true, false].into_iter().enumerate() {
+            let y = MATERIAL_Y + row as f32*MATERIAL_ROW_HEIGHT;
+            let captured = self.captured_by(white);
+            for (i, &piece) in captured.iter().enumerate() {
+                let sprite = &self.pieces[piece-1];
+                let scale = mint::Vector2 {x: CAPTURED_PIECE_SIZE / (sprite.width() as f32), y: CAPTURED_PIECE_SIZE / (sprite.height() as f32)};
+                let dest = mint::Vector2 {x: BORDER_SIZE + i as f32*CAPTURED_PIECE_STEP, y};
+                canvas.draw(sprite, graphics::DrawParam::new().dest(dest).scale(scale));
+            }
+            let lead = if white {difference} else {-difference};
+            let lead = (lead as f32 / 100.0).round() as i32;
+            if lead > 0 {
+                let x = BORDER_SIZE + captured.len() as f32*CAPTURED_PIECE_STEP + CAPTURED_PIECE_SIZE;
+                canvas.draw(&graphics::Text::new(format!("+{}", lead)), Vec2::new(x, y+2.0));
+            }
+        }
+    }
+    fn has_human(&self) -> bool {
+        self.player_white == Player::HUMAN || self.player_black == Player::HUMAN
+    }
+    /// Takes back the last move, and the bot's replies before it so it is a human's turn again.
+    pub fn undo(&mut self) {
+        let mut history = self.history.clone();
+        match history.pop() {
+            Some(last) => self.redo.push(last),
+            None => return,
+        }
+        self.replay(history);
+        while *self.get_current_player_type() == Player::BOT && self.has_human() {
+            let mut history = self.history.clone();
+            match history.pop() {
+                Some(last) => self.redo.push(last),
+                None => break,
+            }
+            self.replay(history);
+        }
+        self.restore_clock();
+    }
+    /// Plays back a move taken back with `undo`, including the bot's reply that was taken back with it.
+    pub fn redo(&mut self) {
+        match self.redo.pop() {
+            Some((from, to)) => self.push_move(from, to),
+            None => return,
+        }
+        while *self.get_current_player_type() == Player::BOT && self.has_human() {
+            match self.redo.pop() {
+                Some((from, to)) => self.push_move(from, to),
+                None => break,
+            }
+        }
+        self.restore_clock();
+        self.selected_square = None;
+        self.view_ply = None;
+        self.view_board = None;
+    }
+    fn push_move(&mut self, old: usize, new: usize) {
+        self.move_names.push(notation::move_to_san(&self.board, old, new));
+        self.captures.push(self.board.piece_at(new));
         self.history.push((old, new));
-        self.board.move_square(old, new);
+        self.board.make_move((old, new));
+        self.move_list_scroll = None;
+    }
+    /// Ends the game once the side to move is checkmated or stalemated.
+    fn check_no_moves(&mut self) {
+        if self.game_over.is_some() || self.board.has_legal_moves() {
+            return;
+        }
+        let white = self.board.is_whites_turn();
+        self.game_over = Some(if self.board.is_in_check(white) {
+            format!("{} wins by checkmate", if white {"Black"} else {"White"})
+        } else {
+            "Draw by stalemate".to_string()
+        });
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
+        }
+    }
+    pub fn move_square(&mut self, old: usize, new: usize) {
+        self.redo.clear(); //a new move makes the undone moves unreachable.
+        let is_white = self.board.is_whites_turn();
+        self.push_move(old, new);
+        if let Some(clock) = &mut self.clock {
+            clock.press(is_white);
+        }
+        self.clocks.resize(self.history.len(), None); //the clocks after undone moves are gone with them.
+        self.clocks.push(self.clock.as_ref().map(clock::Clock::snapshot));
+    }
+    /// Sets the clock back to when the current position was reached and starts it for the side to move.
+    /// Positions without a snapshot, like the moves of a loaded game, keep the time there is now.
+    fn restore_clock(&mut self) {
+        if let Some(Some(snapshot)) = self.clocks.get(self.history.len()) {
+            self.clock = Some(snapshot.clone());
+        }
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.board.is_whites_turn());
+        }
+    }
+    /// Forgets the clocks of earlier positions, the current clock becomes the one of the current position.
+    fn reset_clocks(&mut self) {
+        self.clocks = vec![None; self.history.len()];
+        self.clocks.push(self.clock.as_ref().map(clock::Clock::snapshot));
+    }
+    fn overlay_open(&self) -> bool {
+        self.slot_panel.is_some() || self.new_game_panel.is_some()
+    }
+    /// Throws away the current game and starts a new one from `start_board`.
+    pub fn reset(&mut self, player_white: Player, player_black: Player, start_board: P, time_control: Option<clock::TimeControl>) {
+        self.player_white = player_white;
+        self.player_black = player_black;
+        self.flipped = player_white == Player::BOT && player_black == Player::HUMAN;
+        self.start_board = start_board;
+        self.replay(vec![]);
+        self.redo.clear();
+        self.move_list_scroll = None;
+        self.game_over = None;
+        self.set_time_control(time_control);
+    }
+    fn start_new_game(&mut self) {
+        let panel = match &mut self.new_game_panel {
+            Some(panel) => panel,
+            None => return,
+        };
+        let start_board = if panel.fen.trim().is_empty() {
+            P::start_position()
+        } else {
+            match P::from_fen(&panel.fen) {
+                Ok(board) => board,
+                Err(e) => {
+                    panel.error = Some(e);
+                    return;
+                }
+            }
+        };
+        let (player_white, player_black) = (panel.player_white, panel.player_black);
+        let time_control = TIME_CONTROLS[panel.time_control].and_then(clock::TimeControl::parse);
+        self.bot_think_ms = BOT_STRENGTHS[panel.strength].1;
+        self.new_game_panel = None;
+        self.reset(player_white, player_black, start_board, time_control);
+    }
+    fn click_new_game_panel(&mut self, x: f32, y: f32) {
+        if NewGamePanel::start_button().contains([x, y]) {
+            self.start_new_game();
+            return;
+        }
+        let panel = match &mut self.new_game_panel {
+            Some(panel) => panel,
+            None => return,
+        };
+        if NewGamePanel::cancel_button().contains([x, y]) || !PANEL.contains([x, y]) {
+            self.new_game_panel = None;
+        } else if NewGamePanel::field(0).contains([x, y]) {
+            panel.player_white = NewGamePanel::toggle(panel.player_white);
+        } else if NewGamePanel::field(1).contains([x, y]) {
+            panel.player_black = NewGamePanel::toggle(panel.player_black);
+        } else if NewGamePanel::field(2).contains([x, y]) {
+            panel.strength = (panel.strength+1) % BOT_STRENGTHS.len();
+        } else if NewGamePanel::field(3).contains([x, y]) {
+            panel.time_control = (panel.time_control+1) % TIME_CONTROLS.len();
+        }
+    }
+    fn draw_new_game_panel(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        let panel = match &self.new_game_panel {
+            Some(panel) => panel,
+            None => return Ok(()),
+        };
+        let background = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0, 0.0, PANEL.w, PANEL.h),
+            self.theme.panel,
+        )?;
+        canvas.draw(&background, Vec2::new(PANEL.x, PANEL.y));
+        canvas.draw(&graphics::Text::new("New game"), Vec2::new(PANEL.x+BORDER_SIZE, PANEL.y+BORDER_SIZE));
+
+        let options = [
+            ("White", NewGamePanel::player_label(panel.player_white).to_string()),
+            ("Black", NewGamePanel::player_label(panel.player_black).to_string()),
+            ("Bot strength", BOT_STRENGTHS[panel.strength].0.to_string()),
+            ("Time control", TIME_CONTROLS[panel.time_control].unwrap_or("None").to_string()),
+        ];
+        for (row, (label, value)) in options.iter().enumerate() {
+            canvas.draw(&graphics::Text::new(*label), Vec2::new(PANEL.x+BORDER_SIZE, NewGamePanel::row(row)+3.0));
+            draw_button(ctx, canvas, &self.theme, NewGamePanel::field(row), value)?;
+        }
+
+        canvas.draw(&graphics::Text::new("Start position (FEN, empty for the normal start, ctrl+v to paste):"), Vec2::new(PANEL.x+BORDER_SIZE, NewGamePanel::row(4)+3.0));
+        let field = NewGamePanel::fen_field();
+        let outline = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::stroke(1.0),
+            graphics::Rect::new(0.0, 0.0, field.w, field.h),
+            Color::WHITE,
+        )?;
+        canvas.draw(&outline, Vec2::new(field.x, field.y));
+        let mut fen = graphics::Text::new(format!("{}_", panel.fen));
+        fen.set_scale(13.0);
+        canvas.draw(&fen, Vec2::new(field.x+4.0, field.y+4.0));
+        if let Some(error) = &panel.error {
+            canvas.draw(&graphics::Text::new(error.as_str()), graphics::DrawParam::new().dest(Vec2::new(PANEL.x+BORDER_SIZE, NewGamePanel::row(6)+3.0)).color(self.theme.check));
+        }
+        draw_button(ctx, canvas, &self.theme, NewGamePanel::start_button(), "Start")?;
+        draw_button(ctx, canvas, &self.theme, NewGamePanel::cancel_button(), "Cancel")?;
+        Ok(())
+    }
+    /// Starts a clock for a new game, or takes it away with `None`.
+    pub fn set_time_control(&mut self, time_control: Option<clock::TimeControl>) {
+        self.clock = time_control.map(clock::Clock::new);
+        self.reset_clocks();
+        if let Some(clock) = &mut self.clock {
+            clock.start(self.board.is_whites_turn());
+        }
+    }
+    /// Ends the game once the side to move ran out of time.
+    fn check_flag(&mut self) {
+        if self.game_over.is_some() {
+            return;
+        }
+        let white = self.board.is_whites_turn();
+        if let Some(clock) = &mut self.clock {
+            if clock.has_flagged(white) {
+                clock.stop();
+                self.game_over = Some(format!("{} lost on time", if white {"White"} else {"Black"}));
+            }
+        }
+    }
+    fn draw_clocks(&self, canvas: &mut graphics::Canvas) {
+        if let Some(clock) = &self.clock {
+            for (row, white) in [true, false].into_iter().enumerate() {
+                let y = CLOCK_Y + row as f32*CLOCK_ROW_HEIGHT;
+                let name = if white {"White"} else {"Black"};
+                let to_move = if self.board.is_whites_turn() == white && self.game_over.is_none() {"<"} else {""};
+                let label = format!("{} {} {}", name, clock::format_time(clock.remaining(white)), to_move);
+                canvas.draw(&graphics::Text::new(label), Vec2::new(BORDER_SIZE, y+2.0));
+            }
+        }
+        if let Some(message) = &self.game_over {
+            canvas.draw(&graphics::Text::new(message.as_str()), Vec2::new(BORDER_SIZE, CLOCK_Y + 2.0*CLOCK_ROW_HEIGHT + 2.0));
+        }
     }
     pub fn get_current_player_type(&self) -> &Player {
-        if self.board.is_whites_turn {
+        if self.board.is_whites_turn() {
             &self.player_white
         } else {
             &self.player_black
         }
     }
-    pub fn new(ctx: &mut Context, player_white: Player, player_black: Player) -> GameResult<Game> {
+    pub fn new(ctx: &mut Context, player_white: Player, player_black: Player) -> GameResult<Game<P>> {
         let mut pieces: Vec<graphics::Image> = Vec::with_capacity(12);
         for colour in 0..2 {
             let colour_label = if colour==0 {"w"} else {"b"};
@@ -302,25 +1191,26 @@ impl Game {
             }
         }
 
+        let theme = Theme::default();
         let size = SQUARE_SIZE;
         let square_light = graphics::Mesh::new_rectangle(
             ctx,
             graphics::DrawMode::fill(),
             graphics::Rect::new(0.0,0.0,size,size),
-            Color::WHITE,
+            theme.light_square,
         )?;
         let square_dark = graphics::Mesh::new_rectangle(
             ctx,
             graphics::DrawMode::fill(),
             graphics::Rect::new(0.0,0.0,size,size),
-            Color::from_rgb(96, 65, 58),
+            theme.dark_square,
         )?;
 
         let highlight = graphics::Mesh::new_rectangle(
             ctx,
             graphics::DrawMode::stroke(5.0),
             graphics::Rect::new(0.0,0.0,size,size),
-            Color::BLACK
+            theme.selected,
         )?;
 
         let square_moveable = graphics::Mesh::new_circle(
@@ -329,7 +1219,35 @@ impl Game {
             Vec2{x: size/2.0, y: size/2.0},
             10.0,
             2.0,
-            Color::BLACK
+            theme.move_target,
+        )?;
+        let square_capturable = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::stroke(5.0),
+            Vec2{x: size/2.0, y: size/2.0},
+            size/2.0-4.0,
+            1.0,
+            theme.capture_target,
+        )?;
+        let square_last_move = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0,0.0,size,size),
+            theme.last_move,
+        )?;
+        let square_check = graphics::Mesh::new_circle(
+            ctx,
+            graphics::DrawMode::fill(),
+            Vec2{x: size/2.0, y: size/2.0},
+            size/2.0,
+            1.0,
+            theme.check,
+        )?;
+        let square_attacked = graphics::Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            graphics::Rect::new(0.0,0.0,size,size),
+            theme.attacked,
         )?;
 
         let pieces: Box<[graphics::Image; 12]> = match pieces.into_boxed_slice().try_into() {
@@ -337,7 +1255,7 @@ impl Game {
             Err(o) => panic!("Expected a Vec of length {} but it was {}", 12, o.len()),
         };
 
-        let board: Board = Board::new();
+        let board = P::start_position();
 
         Ok(Game {
             board,
@@ -347,9 +1265,32 @@ impl Game {
             selected_square: None,
             square_highlight: highlight,
             square_moveable,
+            square_capturable,
+            square_last_move,
+            square_check,
+            square_attacked,
+            theme,
+            show_attacked: false,
             player_white: player_white,
             player_black,
             history: vec![],
+            game_over: None,
+            slot_panel: None,
+            redo: vec![],
+            view_ply: None,
+            view_board: None,
+            branch_mode: BranchMode::BLOCK,
+            move_names: vec![],
+            captures: vec![],
+            move_list_scroll: None,
+            dragging: None,
+            flipped: player_white == Player::BOT && player_black == Player::HUMAN,
+            clock: None,
+            clocks: vec![None],
+            start_board: P::start_position(),
+            bot_think_ms: BOT_STRENGTHS[DEFAULT_BOT_STRENGTH].1,
+            search_context: cpu::SearchContext::new(crate::config::EngineConfig::default(), cpu::random_seed()),
+            new_game_panel: None,
         })
     }
 
@@ -0,0 +1,157 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::{Board, clock};
+use crate::position::Position;
+
+pub const GAMES_DIR: &str = "games/";
+pub const EXTENSION: &str = "txt";
+
+pub struct SaveSlot {
+    pub name: String,
+    pub path: PathBuf,
+    pub modified: SystemTime,
+    pub move_count: usize,
+}
+
+impl SaveSlot {
+    pub fn date(&self) -> String {
+        format_date(self.modified)
+    }
+}
+
+/// A game as stored on disk: an optional clock and start position, then one `from to` line per move.
+pub struct SavedGame<P: Position = Board> {
+    pub start_board: P,
+    pub history: Vec<(usize, usize)>,
+    pub clock: Option<clock::Clock>,
+}
+
+impl<P: Position> SavedGame<P> {
+    pub fn new(start_board: P, history: Vec<(usize, usize)>) -> SavedGame<P> {
+        SavedGame {start_board, history, clock: None}
+    }
+    pub fn parse(contents: &str) -> Result<SavedGame<P>, String> {
+        let mut game = SavedGame::new(P::start_position(), vec![]);
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() {continue;}
+            if line.starts_with("clock") {
+                game.clock = clock::Clock::from_save_line(line);
+                continue;
+            }
+            if let Some(fen) = line.strip_prefix("fen ") {
+                game.start_board = P::from_fen(fen)?;
+                continue;
+            }
+            let squares: Vec<usize> = line.split(' ').map(|n| n.parse::<usize>()).collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+            match squares[..] {
+                [from, to] if from < 64 && to < 64 => game.history.push((from, to)),
+                _ => return Err(format!("Expected a move like `52 36`, got `{}`", line)),
+            }
+        }
+        Ok(game)
+    }
+    pub fn read(path: &str) -> Result<SavedGame<P>, String> {
+        let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        SavedGame::parse(&contents).map_err(|e| format!("Corrupted file {}: {}", path, e))
+    }
+    pub fn to_file_string(&self) -> String {
+        let mut data = "".to_string();
+        if let Some(clock) = &self.clock {
+            data += format!("{}\n", clock.to_save_line()).as_str();
+        }
+        if self.start_board.to_fen() != P::start_position().to_fen() {
+            data += format!("fen {}\n", self.start_board.to_fen()).as_str();
+        }
+        for (from, to) in self.history.iter() {
+            data += format!("{} {}\n", from, to).as_str();
+        }
+        data
+    }
+    /// The position after all moves were played.
+    pub fn final_board(&self) -> P {
+        let mut board = self.start_board.clone();
+        for &(from, to) in self.history.iter() {
+            board.make_move((from, to));
+        }
+        board
+    }
+}
+
+/// All saved games in `GAMES_DIR`, newest first.
+pub fn list_slots() -> Vec<SaveSlot> {
+    let mut slots: Vec<SaveSlot> = vec![];
+    let files = match fs::read_dir(GAMES_DIR) {
+        Ok(files) => files,
+        Err(_) => return slots, //no games saved yet.
+    };
+    for file in files.flatten() {
+        let path = file.path();
+        if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+            continue;
+        }
+        let name = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let modified = file.metadata().and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH);
+        let move_count = fs::read_to_string(&path)
+            .map(|contents| contents.lines().filter(|line| line.starts_with(|c: char| c.is_ascii_digit())).count())
+            .unwrap_or(0);
+        slots.push(SaveSlot {name, path, modified, move_count});
+    }
+    slots.sort_by(|a, b| b.modified.cmp(&a.modified));
+    slots
+}
+
+/// Turns whatever the user typed into something that is safe to use as a file name.
+pub fn sanitize_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' {c} else {'_'})
+        .collect()
+}
+
+pub fn slot_path(name: &str) -> String {
+    format!("{}{}.{}", GAMES_DIR, sanitize_name(name), EXTENSION)
+}
+
+/// A name that is not taken yet, used when the user saves without typing one.
+pub fn default_name() -> String {
+    let taken = list_slots();
+    let mut i = taken.len() + 1;
+    loop {
+        let name = format!("game_{}", i);
+        if !taken.iter().any(|slot| slot.name == name) {
+            break name;
+        }
+        i += 1;
+    }
+}
+
+pub fn delete_slot(slot: &SaveSlot) {
+    if let Err(e) = fs::remove_file(&slot.path) {
+        println!("Failed to delete {}: {}", slot.path.display(), e);
+    }
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM` (UTC), without pulling in a date crate.
+pub fn format_date(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0) as i64;
+    let days = secs.div_euclid(86_400);
+    let secs_of_day = secs.rem_euclid(86_400);
+
+    // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe/1460 + doe/36_524 - doe/146_096) / 365;
+    let doy = doe - (365*yoe + yoe/4 - yoe/100);
+    let mp = (5*doy + 2) / 153;
+    let day = doy - (153*mp + 2)/5 + 1;
+    let month = if mp < 10 {mp + 3} else {mp - 9};
+    let year = yoe + era*400 + (month <= 2) as i64;
+
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, secs_of_day/3600, (secs_of_day/60) % 60)
+}
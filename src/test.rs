@@ -1,50 +1,320 @@
 use crate::*;
 use std::time::{SystemTime, UNIX_EPOCH};
-use rand::Rng;
+use crate::position::{Move, Position};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
-pub fn benchmark_performance(game: &mut render::Game) {
-    println!("Going to run all tests in test folder.");
+pub fn benchmark_performance(limits: &cpu::SearchLimits, ctx: &mut cpu::SearchContext) {
+    println!("Going to run all positions of the test suite.");
+    let positions = match epd::read_epd(epd::DEFAULT_SUITE) {
+        Ok(positions) => positions,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
     let bef = SystemTime::now();
     let mut evaluations: usize = 0;
     let runs_on_tests: usize = 10;
-    let mut RNG = rand::thread_rng();
     for i in 0..runs_on_tests {
-        let dir = "test/";
-        match std::fs::read_dir(dir) {
-            Ok(files) => {
-                for file in files {
-                    if let Ok(file) = file {
-                        game.load_game(format!("{}{}", dir, file.file_name().to_str().unwrap()).as_str());
-                        let is_cpu_white = game.board.is_whites_turn;
-                        cpu::calculate_best_move(&mut game.board, &mut evaluations, crate::DEPTH, crate::MAX_DEPTH, -100_000, is_cpu_white, true, &mut RNG);
+        for position in positions.iter() {
+            let result = cpu::search(&mut position.board.clone(), limits, ctx);
+            evaluations += result.evaluations;
+        }
+        println!("Finished {}% of tests.", (1+i)*100 / runs_on_tests);
+    }
+    let elapsed = bef.elapsed().unwrap();
+    println!("Run all test positions, took {} seconds!", elapsed.as_secs());
+    println!("{} evaluations, {} per second.", evaluations, (evaluations as f64 / elapsed.as_secs_f64().max(0.001)) as u64);
+}
+
+/// Searches every position of an EPD suite and checks the move against its `bm` and `am`.
+/// Returns how many positions passed.
+pub fn run_suite(positions: &[epd::EpdPosition], limits: &cpu::SearchLimits, ctx: &mut cpu::SearchContext) -> usize {
+    let mut passed = 0;
+    for position in positions {
+        let result = cpu::search(&mut position.board.clone(), limits, ctx);
+        let (from, to) = result.best_move;
+        let pass = position.passes(result.best_move);
+        passed += pass as usize;
+        let expected = position.best_moves.iter().map(|&(from, to)| notation::move_to_san(&position.board, from, to)).collect::<Vec<String>>();
+        let avoided = position.avoid_moves.iter().map(|&(from, to)| notation::move_to_san(&position.board, from, to)).collect::<Vec<String>>();
+        let mut answer = String::new();
+        if !expected.is_empty() {
+            answer += &format!(" bm {}", expected.join(" "));
+        }
+        if !avoided.is_empty() {
+            answer += &format!(" am {}", avoided.join(" "));
+        }
+        println!("{} {:<12} played {:<8}{}", if pass {"PASS"} else {"FAIL"}, position.id, notation::move_to_san(&position.board, from, to), answer);
+    }
+    println!("Passed {}/{} positions.", passed, positions.len());
+    passed
+}
+
+pub const SEE_SUITE: &str = "test/see.txt";
+
+/// Checks `Board::see` against the positions in `path`, lines of `FEN; SAN move; expected value`.
+/// Returns whether every position gave the expected value.
+pub fn see_suite(path: &str) -> Result<bool, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path, e))?;
+    let lines = text.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('#')).collect::<Vec<&str>>();
+    let mut passed = 0;
+    for line in lines.iter() {
+        let fields = line.split(';').map(|field| field.trim()).collect::<Vec<&str>>();
+        if fields.len() != 3 {
+            return Err(format!("Expected `FEN; move; value`, got `{}`", line));
+        }
+        let board = Board::from_fen(fields[0])?;
+        let mv = notation::san_to_move(&board, fields[1]).ok_or(format!("Illegal move {} in {}", fields[1], fields[0]))?;
+        let expected = fields[2].parse::<i32>().map_err(|_| format!("Expected a value, got `{}`", fields[2]))?;
+        let value = board.see(mv);
+        // `see_ge` has to agree with `see` right at the boundary.
+        let pass = value == expected && board.see_ge(mv, expected) && !board.see_ge(mv, expected+1);
+        passed += pass as usize;
+        println!("{} {:<6} {:>5} expected {:>5}  {}", if pass {"PASS"} else {"FAIL"}, fields[1], value, expected, fields[0]);
+    }
+    println!("Passed {}/{} positions.", passed, lines.len());
+    Ok(passed == lines.len())
+}
+
+/// Number of positions `depth` legal moves away, the standard move generator check.
+pub fn perft<P: Position>(board: &mut P, depth: usize) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    board.legal_moves().into_iter().map(|mv| {
+        let undo = board.make_move(mv);
+        let nodes = perft(board, depth-1);
+        board.unmake_move(undo);
+        nodes
+    }).sum()
+}
+
+/// `perft` split up by the first move, to find where a move generator goes wrong.
+pub fn perft_divide<P: Position>(board: &P, depth: usize) {
+    let bef = SystemTime::now();
+    let mut total = 0;
+    for (from, to) in board.legal_moves() {
+        let mut board_2 = board.clone();
+        board_2.make_move((from, to));
+        let nodes = if depth > 0 {perft(&mut board_2, depth-1)} else {1};
+        println!("{}: {}", notation::move_to_uci(board, from, to), nodes);
+        total += nodes;
+    }
+    println!("\nNodes searched: {}", total);
+    println!("Took {}ms", bef.elapsed().unwrap().as_millis());
+}
+
+/// Plays `games` random games with two board backends side by side and compares them after every move:
+/// the pieces, side to move, castling and en passant, which pieces give check, where every piece can move,
+/// and whether unmaking a move undoes it.
+/// The first position they disagree on is shrunk to as few pieces as still show the difference and printed as FEN.
+/// Returns whether the backends agreed everywhere.
+pub fn differential_test<A: Position, B: Position>(games: usize, max_plies: usize, seed: u64) -> bool {
+    let mut rng = StdRng::seed_from_u64(seed);
+    for game in 0..games {
+        let mut a = A::start_position();
+        let mut b = B::start_position();
+        let mut moves: Vec<Move> = vec![];
+        let mut previous_fen = a.to_fen();
+        for ply in 0..=max_plies {
+            if let Some(difference) = compare(&a, &b) {
+                println!("Game {} went different after {}", game+1, moves.iter().map(|&mv| move_to_text(mv)).collect::<Vec<String>>().join(" "));
+                println!("Position: {}", a.to_fen());
+                println!("{}", difference);
+                // The position on its own may not show it, then it is the last move that went different.
+                let fen = a.to_fen();
+                let (fen, mv) = if differs_from_fen::<A, B>(&fen, None).is_some() {(fen, None)} else {(previous_fen, moves.last().copied())};
+                match fen::Fen::parse(&fen) {
+                    Ok(parsed) if differs_from_fen::<A, B>(&fen, mv).is_some() => {
+                        let shrunk = shrink::<A, B>(parsed, mv).to_string();
+                        println!("\nShrunk: {}", shrunk);
+                        if let Some((from, to)) = mv {
+                            println!("Move: {}", move_to_text((from, to)));
+                        }
+                        println!("{}", differs_from_fen::<A, B>(&shrunk, mv).unwrap_or_default());
                     }
+                    _ => println!("\nCouldn't shrink, the difference doesn't show up when starting from the FEN."),
                 }
-
+                return false;
+            }
+            let legal_moves = a.legal_moves();
+            if ply == max_plies || legal_moves.is_empty() {
+                break;
+            }
+            let mv = legal_moves[rng.gen_range(0..legal_moves.len())];
+            previous_fen = a.to_fen();
+            if let Some(difference) = unmake_difference(&a, mv).or_else(|| unmake_difference(&b, mv)) {
+                println!("Unmaking {} in {} {}", move_to_text(mv), previous_fen, difference);
+                return false;
             }
-            Err(e) => {},
+            a.make_move(mv);
+            b.make_move(mv);
+            moves.push(mv);
+        }
+        if (game+1) % 100 == 0 {
+            println!("{} games agree.", game+1);
         }
-        println!("Finished {}% of tests.", (1+i)*100 / runs_on_tests);
     }
-    println!("Run all test games, took {} seconds!", bef.elapsed().unwrap().as_secs());
+    println!("{} and {} agree on all {} games.", backend_name::<A>(), backend_name::<B>(), games);
+    true
 }
 
-pub fn benchmark_quality(game: &mut render::Game) {
-    let mut wins_new = 0;
-    let mut beta = true;
-    let total_games: usize = 100;
-    for i in 0..total_games {
-        game.load_game("test/start.txt");
-        loop {
-            cpu::make_bot_move(game, beta);
-            if game.board.rate_board().abs() > 80_000 { //checkmate
-                if beta {
-                    wins_new += 1;
-                }
+/// Checks that the evaluation, pawn structure included, favours neither colour: every position has to score the opposite of itself
+/// with the colours swapped, see `fen::Fen::mirrored`, and has to find its own pawn structure in the pawn table.
+/// The positions are those of the EPD suite and every one of `games` random games. Returns whether all of them did.
+pub fn symmetry_test<P: Position>(games: usize, max_plies: usize, seed: u64) -> bool {
+    let mut fens = epd::read_epd(epd::DEFAULT_SUITE).map(|positions| positions.iter().map(|position| position.board.to_fen()).collect()).unwrap_or(vec![]);
+    let mut rng = StdRng::seed_from_u64(seed);
+    for _ in 0..games {
+        let mut board = P::start_position();
+        for _ in 0..max_plies {
+            fens.push(board.to_fen());
+            let legal_moves = board.legal_moves();
+            if legal_moves.is_empty() {
                 break;
             }
-            beta = !beta;
+            board.make_move(legal_moves[rng.gen_range(0..legal_moves.len())]);
+        }
+    }
+    let mut pawn_table = pawns::PawnTable::new();
+    for fen in fens.iter() {
+        let mirrored = match fen::Fen::parse(fen) {
+            Ok(parsed) => parsed.mirrored().to_string(),
+            Err(e) => {
+                println!("{}: {}", fen, e);
+                return false;
+            }
+        };
+        let (board, mirrored_board) = match (P::from_fen(fen), P::from_fen(&mirrored)) {
+            (Ok(board), Ok(mirrored_board)) => (board, mirrored_board),
+            _ => {
+                println!("Couldn't set up {} or its mirror {}", fen, mirrored);
+                return false;
+            }
+        };
+        let (score, mirrored_score) = (board.evaluate_with_pawns(&mut pawn_table), mirrored_board.evaluate_with_pawns(&mut pawn_table));
+        if score != -mirrored_score {
+            println!("{} scores {}", fen, score);
+            println!("{} scores {}, expected {}", mirrored, mirrored_score, -score);
+            return false;
+        }
+        let (white_pawns, black_pawns) = (board.squares_of(crate::PAWN), board.squares_of(crate::PAWN+6));
+        if pawn_table.get(board.pawn_key(), white_pawns, black_pawns) != pawns::PawnStructure::new(white_pawns, black_pawns) {
+            println!("{} gets another pawn structure from the pawn table, its key {:x} collided", fen, board.pawn_key());
+            return false;
+        }
+    }
+    println!("All {} positions score the same for both colours in {}.", fens.len(), backend_name::<P>());
+    true
+}
+
+/// What differs between the two boards, `None` when they agree.
+fn compare<A: Position, B: Position>(a: &A, b: &B) -> Option<String> {
+    let (fen_a, fen_b) = (without_counters(&a.to_fen()), without_counters(&b.to_fen()));
+    if fen_a != fen_b {
+        return Some(format!("{}: {}\n{}: {}", backend_name::<A>(), fen_a, backend_name::<B>(), fen_b));
+    }
+    if a.checkers() != b.checkers() {
+        return Some(format!("Checked by [{}] in {} but by [{}] in {}",
+            squares_to_text(a.checkers()), backend_name::<A>(), squares_to_text(b.checkers()), backend_name::<B>()));
+    }
+    if a.pinned() != b.pinned() {
+        return Some(format!("Pinned are [{}] in {} but [{}] in {}",
+            squares_to_text(a.pinned()), backend_name::<A>(), squares_to_text(b.pinned()), backend_name::<B>()));
+    }
+    for pos in 0..64 {
+        let (moveable_a, moveable_b) = (a.moveable_squares(pos), b.moveable_squares(pos));
+        if moveable_a != moveable_b {
+            let piece = util::FEN_PIECES.as_bytes()[a.piece_at(pos)-1] as char;
+            return Some(format!("{} on {} moves to [{}] in {} but to [{}] in {}", piece, util::pos_to_algebraic(pos),
+                squares_to_text(moveable_a), backend_name::<A>(), squares_to_text(moveable_b), backend_name::<B>()));
         }
-        println!("Completed {}/{} games.", i+1, total_games);
     }
-    println!("Beta won {}/{} games.", wins_new, total_games);
-}
\ No newline at end of file
+    None
+}
+
+/// Sets both backends up from `fen`, plays `mv` if given, and compares them.
+fn differs_from_fen<A: Position, B: Position>(fen: &str, mv: Option<Move>) -> Option<String> {
+    let mut a = A::from_fen(fen).ok()?;
+    let mut b = B::from_fen(fen).ok()?;
+    let (from, to) = match mv {
+        Some(mv) => mv,
+        None => return compare(&a, &b),
+    };
+    if compare(&a, &b).is_some() || a.moveable_squares(from) & (1<<to) == 0 {
+        return None;
+    }
+    a.make_move((from, to));
+    b.make_move((from, to));
+    compare(&a, &b).map(|difference| format!("After {}: {}", move_to_text((from, to)), difference))
+}
+
+/// Takes pieces, castling rights and the en passant square away for as long as the backends still disagree.
+/// Kings and the piece making `mv` stay.
+fn shrink<A: Position, B: Position>(mut fen: fen::Fen, mv: Option<Move>) -> fen::Fen {
+    let still_differs = |fen: &fen::Fen| differs_from_fen::<A, B>(&fen.to_string(), mv).is_some();
+    loop {
+        let mut candidates: Vec<fen::Fen> = vec![];
+        for pos in 0..64 {
+            let piece = fen.board_pos[pos];
+            if piece == 0 || util::PIECE_TO_COLOURLESS[piece] == crate::KING || mv.map_or(false, |(from, _)| from == pos) {
+                continue;
+            }
+            let mut smaller = fen.clone();
+            smaller.board_pos[pos] = 0;
+            if smaller.en_passant == pos {
+                smaller.en_passant = 0;
+            }
+            candidates.push(smaller);
+        }
+        let mut smaller = fen.clone();
+        smaller.has_moved_king_white = true;
+        candidates.push(smaller);
+        let mut smaller = fen.clone();
+        smaller.has_moved_king_black = true;
+        candidates.push(smaller);
+        let mut smaller = fen.clone();
+        smaller.en_passant = 0;
+        candidates.push(smaller);
+
+        match candidates.into_iter().find(|smaller| *smaller != fen && still_differs(smaller)) {
+            Some(smaller) => fen = smaller,
+            None => return fen,
+        }
+    }
+}
+
+/// What making and unmaking `mv` changes on `board`, `None` when it is as before.
+fn unmake_difference<P: Position>(board: &P, mv: Move) -> Option<String> {
+    let mut unmade = board.clone();
+    let undo = unmade.make_move(mv);
+    unmade.unmake_move(undo);
+    let (fen, unmade_fen) = (without_counters(&board.to_fen()), without_counters(&unmade.to_fen()));
+    if fen != unmade_fen {
+        return Some(format!("gives {} in {}", unmade_fen, backend_name::<P>()));
+    }
+    if board.evaluate() != unmade.evaluate() || board.pieces(true) != unmade.pieces(true) || board.pieces(false) != unmade.pieces(false) {
+        return Some(format!("changes the evaluation or piece sets in {}", backend_name::<P>()));
+    }
+    None
+}
+
+fn backend_name<P: Position>() -> &'static str {
+    let name = std::any::type_name::<P>();
+    name.rsplit("::").nth(1).unwrap_or(name)
+}
+
+/// The halfmove clock and move number aren't kept by every backend.
+fn without_counters(fen: &str) -> String {
+    fen.split_whitespace().take(4).collect::<Vec<&str>>().join(" ")
+}
+
+fn move_to_text((from, to): Move) -> String {
+    util::pos_to_algebraic(from) + &util::pos_to_algebraic(to)
+}
+
+fn squares_to_text(squares: u64) -> String {
+    util::BitIter::new(squares).map(util::pos_to_algebraic).collect::<Vec<String>>().join(" ")
+}
@@ -0,0 +1,104 @@
+use crate::*;
+use std::io::{self, BufRead, Write};
+use std::time::SystemTime;
+
+/// Talks the Universal Chess Interface over stdin/stdout, so the engine can be used from chess GUIs.
+/// Searches run on the same thread, `stop` has no effect since a reply is only read once the search is done.
+pub fn run(options: &cli::Options) {
+    let mut board = Board::new();
+    let mut ctx = cpu::SearchContext::new(options.engine.clone(), options.seed);
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first() {
+            Some(&"uci") => {
+                println!("id name hobrin chess");
+                println!("id author hobrin");
+                println!("option name Hash type spin default {} min 1 max 4096", options.hash_mb);
+                println!("option name Threads type spin default {} min 1 max 256", options.threads);
+                for option in ctx.config.uci_options() {
+                    println!("{}", option);
+                }
+                println!("info string seed {}", ctx.seed);
+                println!("uciok");
+            }
+            Some(&"isready") => println!("readyok"),
+            Some(&"ucinewgame") => board = Board::new(),
+            Some(&"position") => match parse_position(&words[1..]) {
+                Ok(position) => board = position,
+                Err(e) => println!("info string {}", e),
+            },
+            Some(&"setoption") => set_option(&mut ctx.config, &words[1..]),
+            Some(&"go") => go(&mut board, &words[1..], options, &mut ctx),
+            Some(&"d") => println!("{}", board.to_fen()),
+            Some(&"quit") => break,
+            _ => {}, //unknown commands and `stop` are ignored.
+        }
+        let _ = io::stdout().flush();
+    }
+}
+
+/// `startpos` or `fen <fen>`, optionally followed by `moves <move>...`.
+pub fn parse_position(words: &[&str]) -> Result<Board, String> {
+    let moves_idx = words.iter().position(|&word| word == "moves").unwrap_or(words.len());
+    let mut board = match words.first() {
+        Some(&"startpos") => Board::new(),
+        Some(&"fen") => Board::from_fen(&words[1..moves_idx].join(" "))?,
+        _ => return Err("Expected `startpos` or `fen`".to_string()),
+    };
+    for text in words.iter().skip(moves_idx+1) {
+        match notation::uci_to_move(&board, text) {
+            Some((from, to)) => {board.move_square(from, to);},
+            None => return Err(format!("Illegal or unsupported move {}", text)),
+        }
+    }
+    Ok(board)
+}
+
+/// `name <name> value <value>`, names may contain spaces.
+fn set_option(config: &mut config::EngineConfig, words: &[&str]) {
+    let value_idx = words.iter().position(|&word| word == "value").unwrap_or(words.len());
+    let name = words.get(1..value_idx).unwrap_or(&[]).join(" ");
+    let value = words.get(value_idx+1..).unwrap_or(&[]).join(" ");
+    if matches!(name.to_lowercase().as_str(), "hash" | "threads") {
+        return; //accepted for GUIs, nothing uses them yet.
+    }
+    if let Err(e) = config.set(&name, &value) {
+        println!("info string {}", e);
+    }
+}
+
+fn go(board: &mut Board, words: &[&str], options: &cli::Options, ctx: &mut cpu::SearchContext) {
+    let value = |name: &str| -> Option<u64> {
+        let idx = words.iter().position(|&word| word == name)?;
+        words.get(idx+1)?.parse::<u64>().ok()
+    };
+    let white = board.is_whites_turn;
+    let (time, increment) = if white {(value("wtime"), value("winc"))} else {(value("btime"), value("binc"))};
+    let limits = if let Some(depth) = value("depth") {
+        cpu::SearchLimits::depth(depth as usize)
+    } else if let Some(movetime) = value("movetime") {
+        cpu::SearchLimits::think_time(movetime)
+    } else if let Some(time) = time {
+        let moves_to_go = value("movestogo").unwrap_or(30) as usize;
+        cpu::SearchLimits::think_time(clock::think_time_ms(time, increment.unwrap_or(0), moves_to_go))
+    } else {
+        options.search_limits()
+    };
+
+    if !board.has_legal_moves() {
+        println!("bestmove 0000");
+        return;
+    }
+    let bef = SystemTime::now();
+    let result = cpu::search(board, &limits, ctx);
+    let (from, to) = result.best_move;
+    let best_move = notation::move_to_uci(board, from, to);
+    println!("info depth {} score cp {} nodes {} time {} pv {}",
+        result.depth, result.score, result.evaluations, bef.elapsed().unwrap().as_millis(), best_move);
+    println!("bestmove {}", best_move);
+}
@@ -12,10 +12,7 @@ use crate::cpu;
 extern crate lazy_static;
 use lazy_static::lazy_static;
 
-extern crate bitintr;
-use bitintr::*;
 
-use std::arch::asm;
 use std::borrow::BorrowMut;
 use std::sync::RwLock;
 use std::sync::Once;
@@ -28,7 +25,7 @@ use ggez::{
     graphics::{self, Color},
     Context, GameResult,
 };
-use std::ops::{Add, Mul};
+use std::ops::Add;
 use std::{env, path};
 
 pub const DIRECTIONS: [Vec2; 4] = [Vec2{x: 1.0, y: 0.0}, Vec2{x: -1.0, y: 0.0}, Vec2{x: 0.0, y: 1.0}, Vec2{x: 0.0, y: -1.0}];
@@ -49,7 +46,85 @@ pub const PIECE_VALUES: [i32; 13] = [
     -100, //black pawn
 ];
 
-pub const PIECE_VALUES_POSITION: [[i32; 64]; 13] = [[ //no nothing
+/// How much each piece counts towards the game still being in its middle, `MAX_PHASE` with every piece on the board.
+pub const PHASE_WEIGHTS: [i32; 13] = [0, 0, 4, 1, 1, 2, 0, 0, 4, 1, 1, 2, 0];
+pub const MAX_PHASE: i32 = 24;
+
+/// `MIDGAME_POSITION` with the material included, and mirrored for black.
+pub const PIECE_VALUES_MIDGAME: [[i32; 64]; 13] = with_material(MIDGAME_POSITION);
+
+/// Piece-square tables of the white pieces for the middlegame.
+const MIDGAME_POSITION: [[i32; 64]; 7] = [[ //no nothing
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+], [ //king
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -30, -40, -40, -50, -50, -40, -40, -30,
+    -20, -30, -30, -40, -40, -30, -30, -20,
+    -10, -20, -20, -20, -20, -20, -20, -10,
+     20,  20,   0,   0,   0,   0,  20,  20,
+     20,  30,  10,   0,   0,  10,  30,  20,
+], [ //queen
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+     -5,   0,   5,   5,   5,   5,   0,  -5,
+      0,   0,   5,   5,   5,   5,   0,  -5,
+    -10,   5,   5,   5,   5,   5,   0, -10,
+    -10,   0,   5,   0,   0,   0,   0, -10,
+    -20, -10, -10,  50,  -5, -10, -10, -20,
+], [ //bishop
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,   5,  10,  10,   5,   0, -10,
+    -10,   5,   5,  10,  10,   5,   5, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,  10,  10,  10,  10,  10,  10, -10,
+    -10,   5,   0,   0,   0,   0,   5, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+], [ //knight
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   5,  15,  20,  20,  15,   5, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   5,  10,  15,  15,  10,   5, -30,
+    -40, -20,   0,   5,   5,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+], [ //rook
+      0,   0,   0,   0,   0,   0,   0,   0,
+      5,  10,  10,  10,  10,  10,  10,   5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+     -5,   0,   0,   0,   0,   0,   0,  -5,
+      0,   0,   0,   5,   5,   0,   0,   0,
+], [ //pawn
+      0,   0,   0,   0,   0,   0,   0,   0,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     10,  10,  20,  30,  30,  20,  10,  10,
+      5,   5,  10,  25,  25,  10,   5,   5,
+      0,   0,   0,  20,  20,   0,   0,   0,
+      5,  -5, -10,   0,   0, -10,  -5,   5,
+      5,  10,  10, -20, -20,  10,  10,   5,
+      0,   0,   0,   0,   0,   0,   0,   0,
+],];
+
+/// `ENDGAME_POSITION` with the material included, and mirrored for black.
+pub const PIECE_VALUES_ENDGAME: [[i32; 64]; 13] = with_material(ENDGAME_POSITION);
+
+/// Piece-square tables of the white pieces for when few pieces are left:
+/// the king heads for the centre and pawns are worth more the closer they are to promoting.
+const ENDGAME_POSITION: [[i32; 64]; 7] = [[ //no nothing
     0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0,
@@ -58,119 +133,78 @@ pub const PIECE_VALUES_POSITION: [[i32; 64]; 13] = [[ //no nothing
     0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0,
-], [ //white king
-    100_000+-30, 100_000+-40, 100_000+-40, 100_000+-50, 100_000+-50, 100_000+-40, 100_000+-40, 100_000+-30,
-    100_000+-30, 100_000+-40, 100_000+-40, 100_000+-50, 100_000+-50, 100_000+-40, 100_000+-40, 100_000+-30,
-    100_000+-30, 100_000+-40, 100_000+-40, 100_000+-50, 100_000+-50, 100_000+-40, 100_000+-40, 100_000+-30,
-    100_000+-30, 100_000+-40, 100_000+-40, 100_000+-50, 100_000+-50, 100_000+-40, 100_000+-40, 100_000+-30,
-    100_000+-20, 100_000+-30, 100_000+-30, 100_000+-40, 100_000+-40, 100_000+-30, 100_000+-30, 100_000+-20,
-    100_000+-10, 100_000+-20, 100_000+-20, 100_000+-20, 100_000+-20, 100_000+-20, 100_000+-20, 100_000+-10,
-    100_000+20,  100_000+20,  100_000+ 0,  100_000+ 0,  100_000+ 0,  100_000+ 0,  100_000+20,  100_000+20,
-    100_000+20,  100_000+30,  100_000+10,  100_000+ 0,  100_000+ 0,  100_000+10,  100_000+30,  100_000+20,
-], [ //white queen
-    900+-20, 900+-10, 900+-10,  900+-5,  900+-5, 900+-10, 900+-10, 900+-20,
-    900+-10, 900+  0, 900+  0,  900+ 0,  900+ 0, 900+  0, 900+  0, 900+-10,
-    900+-10, 900+  0, 900+  5,  900+ 5,  900+ 5, 900+  5, 900+  0, 900+-10,
-    900+ -5, 900+  0, 900+  5,  900+ 5,  900+ 5, 900+  5, 900+  0, 900+ -5,
-    900+  0, 900+  0, 900+  5,  900+ 5,  900+ 5, 900+  5, 900+  0, 900+ -5,
-    900+-10, 900+  5, 900+  5,  900+ 5,  900+ 5, 900+  5, 900+  0, 900+-10,
-    900+-10, 900+  0, 900+  5,  900+ 0,  900+ 0, 900+  0, 900+  0, 900+-10,
-    900+-20, 900+-10, 900+-10,  900+50,  900+-5, 900+-10, 900+-10, 900+-20,
-], [ //white bishop
-    320+-20, 320+-10, 320+-10, 320+-10, 320+-10, 320+-10, 320+-10, 320+-20,
-    320+-10, 320+  0, 320+  0, 320+  0, 320+  0, 320+  0, 320+  0, 320+-10,
-    320+-10, 320+  0, 320+  5, 320+ 10, 320+ 10, 320+  5, 320+  0, 320+-10,
-    320+-10, 320+  5, 320+  5, 320+ 10, 320+ 10, 320+  5, 320+  5, 320+-10,
-    320+-10, 320+  0, 320+ 10, 320+ 10, 320+ 10, 320+ 10, 320+  0, 320+-10,
-    320+-10, 320+ 10, 320+ 10, 320+ 10, 320+ 10, 320+ 10, 320+ 10, 320+-10,
-    320+-10, 320+  5, 320+  0, 320+  0, 320+  0, 320+  0, 320+  5, 320+-10,
-    320+-20, 320+-10, 320+-10, 320+-10, 320+-10, 320+-10, 320+-10, 320+-20,
-],
-[ //white knight
-    300+-50, 300+-40, 300+-30, 300+-30, 300+-30, 300+-30, 300+-40, 300+-50,
-    300+-40, 300+-20, 300+  0, 300+  0, 300+  0, 300+  0, 300+-20, 300+-40,
-    300+-30, 300+  0, 300+ 10, 300+ 15, 300+ 15, 300+ 10, 300+  0, 300+-30,
-    300+-30, 300+  5, 300+ 15, 300+ 20, 300+ 20, 300+ 15, 300+  5, 300+-30,
-    300+-30, 300+  0, 300+ 15, 300+ 20, 300+ 20, 300+ 15, 300+  0, 300+-30,
-    300+-30, 300+  5, 300+ 10, 300+ 15, 300+ 15, 300+ 10, 300+  5, 300+-30,
-    300+-40, 300+-20, 300+  0, 300+  5, 300+  5, 300+  0, 300+-20, 300+-40,
-    300+-50, 300+-40, 300+-30, 300+-30, 300+-30, 300+-30, 300+-40, 300+-50,
-],[ //white rook
-   500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0,
-   500+ 5, 500+10, 500+10, 500+10, 500+10, 500+10, 500+10, 500+ 5,
-   500+-5, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+-5,
-   500+-5, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+-5,
-   500+-5, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+-5,
-   500+-5, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+-5,
-   500+-5, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+ 0, 500+-5,
-   500+ 0, 500+ 0, 500+ 0, 500+ 5, 500+ 5, 500+ 0, 500+ 0, 500+ 0,
-],
-[ //white pawn
-   100+ 0, 100+ 0,100+  0,100+  0,100+  0,100+  0,100+  0,100+  0,
-   100+50, 100+50,100+ 50,100+ 50,100+ 50,100+ 50,100+ 50,100+ 50,
-   100+10, 100+10,100+ 20,100+ 30,100+ 30,100+ 20,100+ 10,100+ 10,
-   100+ 5, 100+ 5,100+ 10,100+ 25,100+ 25,100+ 10,100+  5,100+  5,
-   100+ 0, 100+ 0,100+  0,100+ 20,100+ 20,100+  0,100+  0,100+  0,
-   100+ 5, 100+-5,100+-10,100+  0,100+  0,100+-10,100+ -5,100+  5,
-   100+ 5, 100+10,100+ 10,100+-20,100+-20,100+ 10,100+ 10,100+  5,
-   100+ 0, 100+ 0,100+  0,100+  0,100+  0,100+  0,100+  0,100+  0,
-],
-[ //black king
-    -100_000-20,  -100_000-30,  -100_000-10,  -100_000- 0,  -100_000- 0,  -100_000-10,  -100_000-30,  -100_000-20,
-    -100_000-20,  -100_000-20,  -100_000- 0,  -100_000- 0,  -100_000- 0,  -100_000- 0,  -100_000-20,  -100_000-20,
-    -100_000--10, -100_000--20, -100_000--20, -100_000--20, -100_000--20, -100_000--20, -100_000--20, -100_000--10,
-    -100_000--20, -100_000--30, -100_000--30, -100_000--40, -100_000--40, -100_000--30, -100_000--30, -100_000--20,
-    -100_000--20, -100_000--40, -100_000--40, -100_000--50, -100_000--50, -100_000--40, -100_000--40, -100_000--20,
-    -100_000--20, -100_000--40, -100_000--40, -100_000--50, -100_000--50, -100_000--40, -100_000--40, -100_000--20,
-    -100_000--30, -100_000--40, -100_000--40, -100_000--50, -100_000--50, -100_000--40, -100_000--40, -100_000--30,
-    -100_000--30, -100_000--40, -100_000--40, -100_000--50, -100_000--50, -100_000--40, -100_000--40, -100_000--30,
-], [ //black queen
-    -900--20, -900--10, -900--10,  -900-40,  -900--5, -900--10, -900--10, -900--20,
-    -900--10, -900-  0, -900-  0,  -900- 0,  -900- 0, -900-  0, -900-  0, -900--10,
-    -900--10, -900-  0, -900-  5,  -900- 5,  -900- 5, -900-  5, -900-  0, -900--10,
-    -900- -5, -900-  0, -900-  5,  -900- 5,  -900- 5, -900-  5, -900-  0, -900- -5,
-    -900-  0, -900-  0, -900-  5,  -900- 5,  -900- 5, -900-  5, -900-  0, -900- -5,
-    -900--10, -900-  5, -900-  5,  -900- 5,  -900- 5, -900-  5, -900-  0, -900--10,
-    -900--10, -900-  0, -900-  5,  -900- 0,  -900- 0, -900-  0, -900-  0, -900--10,
-    -900--20, -900--10, -900--10,  -900--5,  -900--5, -900--10, -900--10, -900--20,
-], [ //black knight
-    -300--50, -300--40, -300--30, -300--30, -300--30, -300--30, -300--40, -300--50,
-    -300--40, -300--20, -300-  0, -300-  5, -300-  5, -300-  0, -300--20, -300--40,
-    -300--30, -300-  5, -300- 10, -300- 15, -300- 15, -300- 10, -300-  5, -300--30,
-    -300--30, -300-  0, -300- 15, -300- 20, -300- 20, -300- 15, -300-  0, -300--30,
-    -300--30, -300-  5, -300- 15, -300- 20, -300- 20, -300- 15, -300-  5, -300--30,
-    -300--30, -300-  0, -300- 10, -300- 15, -300- 15, -300- 10, -300-  0, -300--30,
-    -300--40, -300--20, -300-  0, -300-  0, -300-  0, -300-  0, -300--20, -300--40,
-    -300--50, -300--40, -300--30, -300--30, -300--30, -300--30, -300--40, -300--50,
-], [ //black bishop
-    -320--20, -320--10, -320--10, -320--10, -320--10, -320--10, -320--10, -320--20,
-    -320--10, -320-  5, -320-  0, -320-  0, -320-  0, -320-  0, -320-  5, -320--10,
-    -320--10, -320- 10, -320- 10, -320- 10, -320- 10, -320- 10, -320- 10, -320--10,
-    -320--10, -320-  0, -320- 10, -320- 10, -320- 10, -320- 10, -320-  0, -320--10,
-    -320--10, -320-  5, -320-  5, -320- 10, -320- 10, -320-  5, -320-  5, -320--10,
-    -320--10, -320-  0, -320-  5, -320- 10, -320- 10, -320-  5, -320-  0, -320--10,
-    -320--10, -320-  0, -320-  0, -320-  0, -320-  0, -320-  0, -320-  0, -320--10,
-    -320--20, -320--10, -320--10, -320--10, -320--10, -320--10, -320--10, -320--20,
-], [ //black rook
-    -500- 0, -500- 0, -500- 0, -500- 5, -500- 5, -500- 0, -500- 0, -500- 0,
-    -500--5, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500--5,
-    -500--5, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500--5,
-    -500--5, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500--5,
-    -500--5, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500--5,
-    -500--5, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500--5,
-    -500- 5, -500-10, -500-10, -500-10, -500-10, -500-10, -500-10, -500- 5,
-    -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0, -500- 0,
-], [ //black pawn
-   -100- 0, -100- 0,-100-  0,-100-  0,-100-  0,-100-  0,-100-  0,-100-  0,
-   -100- 5, -100-10,-100- 10,-100--20,-100--20,-100- 10,-100- 10,-100-  5,
-   -100- 5, -100--5,-100--10,-100-  0,-100-  0,-100--10,-100- -5,-100-  5,
-   -100- 0, -100- 0,-100-  0,-100- 20,-100- 20,-100-  0,-100-  0,-100-  0,
-   -100- 5, -100- 5,-100- 10,-100- 25,-100- 25,-100- 10,-100-  5,-100-  5,
-   -100-10, -100-10,-100- 20,-100- 30,-100- 30,-100- 20,-100- 10,-100- 10,
-   -100-50, -100-50,-100- 50,-100- 50,-100- 50,-100- 50,-100- 50,-100- 50,
-   -100- 0, -100- 0,-100-  0,-100-  0,-100-  0,-100-  0,-100-  0,-100-  0,
+], [ //king
+    -50, -40, -30, -20, -20, -30, -40, -50,
+    -30, -20, -10,   0,   0, -10, -20, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  30,  40,  40,  30, -10, -30,
+    -30, -10,  20,  30,  30,  20, -10, -30,
+    -30, -30,   0,   0,   0,   0, -30, -30,
+    -50, -30, -30, -30, -30, -30, -30, -50,
+], [ //queen
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -10,   5,  10,  10,  10,  10,   5, -10,
+     -5,   5,  10,  15,  15,  10,   5,  -5,
+     -5,   5,  10,  15,  15,  10,   5,  -5,
+    -10,   5,  10,  10,  10,  10,   5, -10,
+    -10,   0,   5,   5,   5,   5,   0, -10,
+    -20, -10, -10,  -5,  -5, -10, -10, -20,
+], [ //bishop
+    -20, -10, -10, -10, -10, -10, -10, -20,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,   0,  10,  15,  15,  10,   0, -10,
+    -10,   0,  10,  15,  15,  10,   0, -10,
+    -10,   0,  10,  10,  10,  10,   0, -10,
+    -10,   0,   0,   0,   0,   0,   0, -10,
+    -20, -10, -10, -10, -10, -10, -10, -20,
+], [ //knight
+    -50, -40, -30, -30, -30, -30, -40, -50,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   0,  15,  20,  20,  15,   0, -30,
+    -30,   0,  10,  15,  15,  10,   0, -30,
+    -40, -20,   0,   0,   0,   0, -20, -40,
+    -50, -40, -30, -30, -30, -30, -40, -50,
+], [ //rook
+     10,  10,  10,  10,  10,  10,  10,  10,
+     15,  15,  15,  15,  15,  15,  15,  15,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+], [ //pawn
+      0,   0,   0,   0,   0,   0,   0,   0,
+     80,  80,  80,  80,  80,  80,  80,  80,
+     50,  50,  50,  50,  50,  50,  50,  50,
+     25,  25,  25,  25,  25,  25,  25,  25,
+     10,  10,  10,  10,  10,  10,  10,  10,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
+      0,   0,   0,   0,   0,   0,   0,   0,
 ],];
 
+/// Adds the material to tables of the white pieces and fills in black's, which are white's upside down and negated.
+const fn with_material(white: [[i32; 64]; 7]) -> [[i32; 64]; 13] {
+    let mut tables = [[0; 64]; 13];
+    let mut piece = 1;
+    while piece <= 6 {
+        let mut pos = 0;
+        while pos < 64 {
+            tables[piece][pos] = PIECE_VALUES[piece] + white[piece][pos];
+            tables[piece+6][pos ^ 56] = PIECE_VALUES[piece+6] - white[piece][pos]; //same file, rank from the other side.
+            pos += 1;
+        }
+        piece += 1;
+    }
+    tables
+}
+
 
 lazy_static! {
     pub static ref KNIGHT_MOVES: [u64; 64] = {
@@ -209,183 +243,6 @@ lazy_static! {
         }
         moves
     };
-    pub static ref ROOK_MOVES: [u64; 64] = {
-        let mut moves = [0u64; 64];
-        for pos in 0..64 {
-            let (x, y) = pos_to_xy(pos);
-            let mut cur_move: u64 = 0;
-            for dir_dir in DIRECTIONS.iter() {
-                let mut dir: Vec2 = Vec2 {x: 6.9, y: 6.9}; //Never actually gets run, shitcode
-                let mut i = 1;
-                loop {
-                    dir = pos_to_vec(pos).add(dir_dir.mul(i as f32));
-                    if dir.x < 0.0 || dir.y < 0.0 || dir.x > 7.0 || dir.y > 7.0 {
-                        break;
-                    }
-                    cur_move |= 1 << (dir.x as usize + (dir.y as usize*8));
-                    i+=1;
-                }
-            }
-            moves[pos] = cur_move;
-        }
-        moves
-    };
-    pub static ref ROOK_OBSTRUCTION_SELF_MAP: Vec<Vec<u64>> = {
-        let mut obstruct_map: Vec<Vec<u64>> = vec![];
-        for pos in 0..64 {
-            obstruct_map.push(vec![]);
-            let (x, y) = util::pos_to_xy(pos);
-            for obstruction_idx  in 0..(1u64<<14) {
-                let mut cur_move: u64 = 0;
-                let bounds_rev: Vec<_> = vec![
-                    (0, y),
-                    (y, (y+x)),
-                ];
-                let bounds = vec![
-                    ((y+x), (y+7)),
-                    ((y+7), 14),
-                ];
-                for i in 0..2 {
-                    for cur_bit in (bounds_rev[i].0..bounds_rev[i].1).rev() {
-                        if obstruction_idx & (1<<cur_bit) != 0 {
-                            break; }
-                        cur_move |= (1<<(cur_bit)).pdep(util::ROOK_MOVES[pos]);
-                    }
-                }
-                for i in 0..2 {
-                    for cur_bit in bounds[i].0..bounds[i].1 {
-                        if obstruction_idx & (1<<cur_bit) != 0 {
-                            break;
-                        }
-                        cur_move |= (1<<(cur_bit)).pdep(util::ROOK_MOVES[pos]);
-                    }
-                }
-                obstruct_map[pos].push(cur_move);
-                // moves[pos][obstruction_idx as usize] = cur_move;
-            }
-        }
-        println!("initialised rook self obstruction!");
-        obstruct_map
-    };
-    pub static ref ROOK_OBSTRUCTION_OPPONENT_MAP: Vec<Vec<u64>> = {
-        let mut obstruct_map: Vec<Vec<u64>> = vec![];
-        for pos in 0..64 {
-            obstruct_map.push(vec![]);
-            let (x, y) = util::pos_to_xy(pos);
-            for obstruction_idx  in 0..(1u64<<14) {
-                let mut cur_move: u64 = 0;
-                let bounds_rev: Vec<_> = vec![
-                    (0, y),
-                    (y, (y+x)),
-                ];
-                let bounds = vec![
-                    ((y+x), (y+7)),
-                    ((y+7), 14),
-                ];
-                for i in 0..2 {
-                    for cur_bit in (bounds_rev[i].0..bounds_rev[i].1).rev() {
-                        cur_move |= (1<<(cur_bit)).pdep(util::ROOK_MOVES[pos]);
-                        if obstruction_idx & (1<<cur_bit) != 0 {
-                            break;
-                        }
-                    }
-                }
-                for i in 0..2 {
-                    for cur_bit in bounds[i].0..bounds[i].1 {
-                        cur_move |= (1<<(cur_bit)).pdep(util::ROOK_MOVES[pos]);
-                        if obstruction_idx & (1<<cur_bit) != 0 {
-                            break;
-                        }
-                    }
-                }
-                obstruct_map[pos].push(cur_move);
-                // moves[pos][obstruction_idx as usize] = cur_move;
-            }
-        }
-        println!("initialised rook obstruction!");
-        obstruct_map
-    };
-    pub static ref BISHOP_MOVES: [u64; 64] = {
-        let mut moves = [0u64; 64];
-        for pos in 0..64 {
-            let (x, y) = pos_to_xy(pos);
-            let mut cur_move: u64 = 0;
-            for dir_dir in DIAGONALS.iter() {
-                let mut dir: Vec2 = Vec2 {x: 6.9, y: 6.9}; //Never actually gets run, shitcode
-                let mut i = 1;
-                loop {
-                    dir = pos_to_vec(pos).add(dir_dir.mul(i as f32));
-                    if dir.x < 0.0 || dir.y < 0.0 || dir.x > 7.0 || dir.y > 7.0 {
-                        break;
-                    }
-                    cur_move |= 1 << (dir.x as usize + (dir.y as usize*8));
-                    i+=1;
-                }
-            }
-            moves[pos] = cur_move;
-        }
-        moves
-    };
-    pub static ref BISHOP_OBSTRUCTION_SELF_MAP: Vec<Vec<u64>> = {
-        let mut obstruct_map: Vec<Vec<u64>> = vec![];
-        for pos in 0..64 {
-            obstruct_map.push(vec![]);
-            let (x, y) = util::pos_to_xy(pos);
-            let count = BISHOP_MOVES[pos].count_ones();
-            for obstruction_idx  in 0..(1u64<<count) {
-                let obstuction_reconstructed = obstruction_idx.pdep(BISHOP_MOVES[pos]);
-                let mut cur_move: u64 = 0;
-                for dir_dir in DIAGONALS.iter() {
-                    let mut look_pos: Vec2 = Vec2 {x: 6.9, y: 6.9}; //Never actually gets run, shitcode
-                    let mut i = 1;
-                    loop {
-                        look_pos = pos_to_vec(pos).add(dir_dir.mul(i as f32));
-                        if look_pos.x < 0.0 || look_pos.y < 0.0 || look_pos.x > 7.0 || look_pos.y > 7.0 {
-                            break;
-                        }
-                        if 0 != (obstuction_reconstructed & (1<<(look_pos.x as usize + (look_pos.y as usize*8)))) {
-                            break;
-                        }
-                        cur_move |= 1 << (look_pos.x as usize + (look_pos.y as usize*8));
-                        i+=1;
-                    }
-                }
-                obstruct_map[pos].push(cur_move);
-            }
-        }
-        println!("initialised bishop self obstruction!");
-        obstruct_map
-    };
-    pub static ref BISHOP_OBSTRUCTION_OPPONENT_MAP: Vec<Vec<u64>> = {
-        let mut obstruct_map: Vec<Vec<u64>> = vec![];
-        for pos in 0..64 {
-            obstruct_map.push(vec![]);
-            let (x, y) = util::pos_to_xy(pos);
-            let count = BISHOP_MOVES[pos].count_ones();
-            for obstruction_idx  in 0..(1u64<<count) {
-                let obstuction_reconstructed = obstruction_idx.pdep(BISHOP_MOVES[pos]);
-                let mut cur_move: u64 = 0;
-                for dir_dir in DIAGONALS.iter() {
-                    let mut look_pos: Vec2 = Vec2 {x: 6.9, y: 6.9}; //Never actually gets run, shitcode
-                    let mut i = 1;
-                    loop {
-                        look_pos = pos_to_vec(pos).add(dir_dir.mul(i as f32));
-                        if look_pos.x < 0.0 || look_pos.y < 0.0 || look_pos.x > 7.0 || look_pos.y > 7.0 {
-                            break;
-                        }
-                        cur_move |= 1 << (look_pos.x as usize + (look_pos.y as usize*8));
-                        if 0 != (obstuction_reconstructed & (1<<(look_pos.x as usize + (look_pos.y as usize*8)))) {
-                            break;
-                        }
-                        i+=1;
-                    }
-                }
-                obstruct_map[pos].push(cur_move);
-            }
-        }
-        println!("initialised bishop obstruction!");
-        obstruct_map
-    };
     pub static ref WHITE_PAWN_CAPTURES: [u64; 64] = {
         let mut moves = [0u64; 64];
         let forward_dir: Vec2 = Vec2 {x: 0.0, y: -1.0};
@@ -448,6 +305,23 @@ pub fn pos_to_xy(pos: usize) -> (usize, usize) {
     // POS_TO_XY[pos]
 }
 
+/// Square name as used in chess notation, `pos` 0 being a8 and 63 being h1.
+pub fn pos_to_algebraic(pos: usize) -> String {
+    let (x, y) = pos_to_xy(pos);
+    format!("{}{}", (b'a' + x as u8) as char, 8-y)
+}
+
+pub fn algebraic_to_pos(square: &str) -> Option<usize> {
+    let bytes = square.as_bytes();
+    if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || !(b'1'..=b'8').contains(&bytes[1]) {
+        return None;
+    }
+    Some((bytes[0] - b'a') as usize + (7 - (bytes[1] - b'1') as usize)*8)
+}
+
+/// FEN letter of every piece, indexed by piece id minus one.
+pub const FEN_PIECES: &str = "KQBNRPkqbnrp";
+
 pub fn pos_to_vec(pos: usize) -> Vec2 {
     let (x, y) = pos_to_xy(pos);
     Vec2 {
@@ -478,18 +352,9 @@ pub fn fix_shr(a: u64, amount: u32) -> u64 {
     a.wrapping_shr(amount) & std::u64::MAX*((amount<64) as u64)
 }
 
+/// Index of the lowest set bit. `trailing_zeros` becomes `tzcnt`/`bsf` on x86_64 and `rbit`+`clz` on aarch64.
 fn get_bsf(bitboard: u64) -> usize {
-    #[cfg(target_arch = "x86_64")] {
-        unsafe {
-            let nr: usize;
-            asm!(
-                "bsf {0}, {1}",
-                out(reg) nr,
-                in(reg) bitboard
-            );
-            nr
-        }
-    }
+    bitboard.trailing_zeros() as usize
 }
 
 pub fn bitboard_to_vec(mut board: u64) -> Vec<usize> {